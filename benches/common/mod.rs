@@ -0,0 +1,29 @@
+//! Inputs shared by the benchmarks.
+
+/// Something shaped like a nightly Bugzilla export: an array of bug records with nested flags.
+pub fn bugzilla_dump(records: usize) -> String {
+    let mut output = String::from("[\n");
+    for id in 0..records {
+        output += &format!(
+            concat!(
+                "  {{\n",
+                "    'bug_id' => {id},\n",
+                "    'summary' => 'Crash when opening attachment #{id} in the \\'classic\\' view',\n",
+                "    \"description\" => \"Steps:\\n\\t1. open\\n\\t2. crash\\n\",\n",
+                "    'product' => q{{Core}},\n",
+                "    'priority' => 'P2',\n",
+                "    'estimated_time' => 1_000.25,\n",
+                "    'resolution' => undef,\n",
+                "    'flags' => [\n",
+                "      {{ 'name' => 'review', 'status' => '?', 'setter' => 'dev{id}@example.com' }},\n",
+                "      {{ 'name' => 'approval', 'status' => '+', 'setter' => undef }},\n",
+                "    ],\n",
+                "    'cc' => [ 1, 2, 3, 4, 5 ],\n",
+                "  }},\n",
+            ),
+            id = id
+        );
+    }
+    output += "]\n";
+    output
+}
@@ -0,0 +1,76 @@
+//! Reports how much heap a parsed dump occupies, rather than how long parsing takes.
+//!
+//! Run with `cargo bench --bench memory`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+#[path = "../common/mod.rs"]
+mod common;
+
+use common::bugzilla_dump;
+
+/// Wraps the system allocator to keep track of live allocations.
+struct Counting;
+
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static LIVE_ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        LIVE_ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        LIVE_ALLOCATIONS.fetch_sub(1, Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        LIVE_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Print the heap held by whatever `parse` returns, while it is still alive.
+fn measure<'a, T>(name: &str, input: &'a str, parse: impl FnOnce(&'a str) -> T) {
+    let bytes = LIVE_BYTES.load(Ordering::Relaxed);
+    let allocations = LIVE_ALLOCATIONS.load(Ordering::Relaxed);
+    let value = parse(input);
+    let bytes = LIVE_BYTES.load(Ordering::Relaxed) - bytes;
+    let allocations = LIVE_ALLOCATIONS.load(Ordering::Relaxed) - allocations;
+    drop(value);
+    println!(
+        "{name:<16} {:>8.1} MiB in {allocations:>9} allocations ({:.2}x input)",
+        bytes as f64 / (1024.0 * 1024.0),
+        bytes as f64 / input.len() as f64,
+    );
+}
+
+fn main() {
+    for records in [1_000, 50_000] {
+        let input = bugzilla_dump(records);
+        println!(
+            "{records} records, {:.1} MiB of input",
+            input.len() as f64 / (1024.0 * 1024.0)
+        );
+        measure("parse", &input, |input| {
+            serde_perl_dumper::parse(input).unwrap()
+        });
+        measure("parse_borrowed", &input, |input| {
+            serde_perl_dumper::parse_borrowed(input).unwrap()
+        });
+        measure("parse_interned", &input, |input| {
+            let mut interner = serde_perl_dumper::KeyInterner::new();
+            let scalar = serde_perl_dumper::parse_interned(input, &mut interner).unwrap();
+            (scalar, interner)
+        });
+    }
+}
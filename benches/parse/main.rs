@@ -0,0 +1,31 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+#[path = "../common/mod.rs"]
+mod common;
+mod nom_grammar;
+
+use common::bugzilla_dump;
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    group.sample_size(10);
+    for records in [1_000, 50_000] {
+        let input = bugzilla_dump(records);
+        group.throughput(Throughput::Bytes(input.len() as u64));
+        group.bench_with_input(BenchmarkId::new("lexer", records), &input, |b, input| {
+            b.iter(|| serde_perl_dumper::parse(input).unwrap())
+        });
+        group.bench_with_input(
+            BenchmarkId::new("lexer_borrowed", records),
+            &input,
+            |b, input| b.iter(|| serde_perl_dumper::parse_borrowed(input).unwrap()),
+        );
+        group.bench_with_input(BenchmarkId::new("nom", records), &input, |b, input| {
+            b.iter(|| nom_grammar::parse(input).unwrap())
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
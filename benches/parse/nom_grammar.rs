@@ -0,0 +1,383 @@
+//! The nom grammar the crate used before the hand-written lexer, kept as a baseline to compare
+//! the lexer against.
+
+#![allow(dead_code)]
+
+#[allow(unused_imports)]
+use nom::{
+    branch::alt,
+    bytes::complete::{escaped, tag},
+    character::complete::{char, digit1, multispace0, multispace1},
+    combinator::{map, map_res, opt},
+    multi::{many0, separated_list0},
+    sequence::{delimited, pair, preceded, terminated, tuple},
+    IResult,
+};
+use nom::{
+    bytes::complete::{escaped_transform, take_while1},
+    character::complete::{none_of, one_of},
+    combinator::value,
+    error::ErrorKind,
+    AsChar, InputTakeAtPosition,
+};
+use std::collections::HashMap;
+
+/// These are all the characters that can be used as delimiters in Perl's `q` operator, I think.
+/// There might be more, and possibly unicode characters, but I don't need those for now.
+const PUNCTUATION: &str = r##"!"#$%&'(*+,-/:;<=?@[\^`{|~"##;
+
+pub fn parse(input: &str) -> Result<Scalar, String> {
+    let (_, scalar) = parse_scalar(input).map_err(|e| format!("{e}"))?;
+    Ok(scalar)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scalar {
+    Undefined,
+    Int(i64),
+    Float(f64),
+    String(String),
+    Reference(Box<Reference>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Hash(pub HashMap<String, Scalar>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Array(pub Vec<Scalar>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Reference {
+    Hash(Box<Hash>),
+    Array(Box<Array>),
+    Scalar(Box<Scalar>),
+}
+
+impl Reference {
+    /// Move any nested references out of this one and onto `stack`, leaving it shallow.
+    fn take_children(&mut self, stack: &mut Vec<Scalar>) {
+        let is_ref = |scalar: &Scalar| matches!(scalar, Scalar::Reference(_));
+        match self {
+            Reference::Hash(hash) => {
+                stack.extend(hash.0.drain().map(|(_, value)| value).filter(is_ref));
+            }
+            Reference::Array(array) => {
+                stack.extend(array.0.drain(..).filter(is_ref));
+            }
+            Reference::Scalar(scalar) => {
+                if is_ref(scalar) {
+                    stack.push(std::mem::replace(&mut **scalar, Scalar::Undefined));
+                }
+            }
+        }
+    }
+}
+
+// The derived drop glue would recurse once per level of nesting, which overflows the stack on
+// deeply nested data. Flatten the tree onto a heap-allocated stack instead.
+impl Drop for Reference {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_children(&mut stack);
+        while let Some(scalar) = stack.pop() {
+            if let Scalar::Reference(mut reference) = scalar {
+                reference.take_children(&mut stack);
+            }
+        }
+    }
+}
+
+/// A container that has been opened but not yet closed.
+enum Frame {
+    Array(Vec<Scalar>),
+    /// The hash built so far and the key of the value being parsed.
+    Hash(HashMap<String, Scalar>, String),
+    ScalarRef,
+}
+
+/// Parse a single scalar, including arbitrarily nested references.
+///
+/// Open containers are kept on an explicit stack rather than the call stack, so the nesting depth
+/// of the input is limited only by available memory.
+fn parse_scalar(mut input: &str) -> IResult<&str, Scalar> {
+    let mut stack = Vec::new();
+
+    loop {
+        let (rest, _) = multispace0(input)?;
+        input = rest;
+
+        let mut scalar = if let Some(rest) = input.strip_prefix('\\') {
+            input = rest;
+            stack.push(Frame::ScalarRef);
+            continue;
+        } else if let Some(rest) = input.strip_prefix('[') {
+            let (rest, _) = multispace0(rest)?;
+            input = rest;
+            if let Some(rest) = input.strip_prefix(']') {
+                input = rest;
+                array_ref(Vec::new())
+            } else {
+                stack.push(Frame::Array(Vec::new()));
+                continue;
+            }
+        } else if let Some(rest) = input.strip_prefix('{') {
+            let (rest, _) = multispace0(rest)?;
+            input = rest;
+            if let Some(rest) = input.strip_prefix('}') {
+                input = rest;
+                hash_ref(HashMap::new())
+            } else {
+                let (rest, key) = parse_hash_key(input)?;
+                input = rest;
+                stack.push(Frame::Hash(HashMap::new(), key));
+                continue;
+            }
+        } else {
+            let (rest, scalar) = parse_literal_scalar(input)?;
+            input = rest;
+            scalar
+        };
+
+        // Fold the finished scalar into its enclosing containers, closing each one that ends here.
+        loop {
+            match stack.pop() {
+                None => return Ok((input, scalar)),
+                Some(Frame::ScalarRef) => {
+                    scalar = Scalar::Reference(Box::new(Reference::Scalar(Box::new(scalar))));
+                }
+                Some(Frame::Array(mut items)) => {
+                    items.push(scalar);
+                    let (rest, more) = list_separator(input, ']')?;
+                    input = rest;
+                    if more {
+                        stack.push(Frame::Array(items));
+                        break;
+                    }
+                    scalar = array_ref(items);
+                }
+                Some(Frame::Hash(mut hash, key)) => {
+                    hash.insert(key, scalar);
+                    let (rest, more) = list_separator(input, '}')?;
+                    input = rest;
+                    if more {
+                        let (rest, key) = parse_hash_key(input)?;
+                        input = rest;
+                        stack.push(Frame::Hash(hash, key));
+                        break;
+                    }
+                    scalar = hash_ref(hash);
+                }
+            }
+        }
+    }
+}
+
+fn array_ref(items: Vec<Scalar>) -> Scalar {
+    Scalar::Reference(Box::new(Reference::Array(Box::new(Array(items)))))
+}
+
+fn hash_ref(hash: HashMap<String, Scalar>) -> Scalar {
+    Scalar::Reference(Box::new(Reference::Hash(Box::new(Hash(hash)))))
+}
+
+/// Parse what follows a list element: either a comma and another element (`true`), or an optional
+/// trailing comma and the `close` delimiter (`false`).
+fn list_separator(input: &str, close: char) -> IResult<&str, bool> {
+    let (input, sep) = opt(comma)(input)?;
+    let (input, _) = multispace0(input)?;
+    match char(close)(input) {
+        Ok((input, _)) => Ok((input, false)),
+        Err(_) if sep.is_some() => Ok((input, true)),
+        Err(e) => Err(e),
+    }
+}
+
+/// Parse a hash key and the `=>` or `,` that separates it from its value.
+fn parse_hash_key(input: &str) -> IResult<&str, String> {
+    let (rest, key) = alt((parse_fatcomma_key, parse_comma_key))(input)?;
+    match key {
+        Scalar::String(key) => Ok((rest, key)),
+        _ => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            ErrorKind::Char,
+        ))),
+    }
+}
+
+fn parse_comma_key(input: &str) -> IResult<&str, Scalar> {
+    let (input, _) = multispace0(input)?;
+    let (input, key) = parse_literal_scalar(input)?;
+    let (input, _) = comma(input)?;
+
+    Ok((input, key))
+}
+
+fn parse_fatcomma_key(input: &str) -> IResult<&str, Scalar> {
+    let (input, _) = multispace0(input)?;
+    let (input, key) = parse_bareword_or_literal(input)?;
+    let (input, _) = multispace0(input)?;
+    let (input, _) = tag("=>")(input)?;
+    let (input, _) = multispace0(input)?;
+
+    Ok((input, key))
+}
+
+fn parse_bareword_or_literal(input: &str) -> IResult<&str, Scalar> {
+    alt((parse_bareword, parse_literal_scalar))(input)
+}
+
+fn parse_bareword(input: &str) -> IResult<&str, Scalar> {
+    let (input, s) = take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_')(input)?;
+
+    Ok((input, Scalar::String(s.to_string())))
+}
+
+fn comma(input: &str) -> IResult<&str, char> {
+    delimited(multispace0, char(','), multispace0)(input)
+}
+
+fn parse_literal_scalar(input: &str) -> IResult<&str, Scalar> {
+    let (input, _) = multispace0(input)?;
+    alt((parse_undef, parse_number, parse_string))(input)
+}
+
+fn parse_undef(input: &str) -> IResult<&str, Scalar> {
+    let (input, _) = tag("undef")(input)?;
+
+    Ok((input, Scalar::Undefined))
+}
+
+fn parse_string(input: &str) -> IResult<&str, Scalar> {
+    alt((
+        parse_single_quoted_string,
+        parse_double_quoted_string,
+        parse_q_string,
+    ))(input)
+}
+
+fn perl_digit1(input: &str) -> IResult<&str, &str> {
+    input.split_at_position1_complete(|item| !is_perl_digit(item), ErrorKind::Digit)
+}
+
+fn is_perl_digit(c: char) -> bool {
+    c.is_dec_digit() || c == '_'
+}
+
+fn parse_number(input: &str) -> IResult<&str, Scalar> {
+    let (rest, (_, frac, exp)) = tuple((
+        pair(opt(char('-')), perl_digit1),
+        opt(preceded(char('.'), perl_digit1)),
+        opt(preceded(one_of("eE"), pair(opt(one_of("+-")), digit1))),
+    ))(input)?;
+
+    let text: String = input[..input.len() - rest.len()]
+        .chars()
+        .filter(|&c| c != '_')
+        .collect();
+    let number = if frac.is_none() && exp.is_none() {
+        // Like Perl, fall back to a float for integers that don't fit in an i64.
+        text.parse::<i64>()
+            .map(Scalar::Int)
+            .or_else(|_| text.parse::<f64>().map(Scalar::Float))
+    } else {
+        text.parse::<f64>().map(Scalar::Float)
+    };
+
+    match number {
+        Ok(number) => Ok((rest, number)),
+        Err(_) => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            ErrorKind::Digit,
+        ))),
+    }
+}
+
+/// Parse the rest of a single-quoted string, up to and including the `close` delimiter. Only the
+/// delimiter and the backslash itself can be escaped; any other backslash is kept as is.
+fn single_quoted_body(input: &str, close: char) -> IResult<&str, String> {
+    let mut body = String::new();
+    let mut chars = input.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == close {
+            return Ok((&input[i + c.len_utf8()..], body));
+        }
+        if c == '\\' {
+            if let Some(next) = chars.clone().next().map(|(_, next)| next) {
+                if next == close || next == '\\' {
+                    chars.next();
+                    body.push(next);
+                    continue;
+                }
+            }
+        }
+        body.push(c);
+    }
+
+    Err(nom::Err::Error(nom::error::Error::new(
+        input,
+        ErrorKind::Char,
+    )))
+}
+
+fn parse_single_quoted_string(input: &str) -> IResult<&str, Scalar> {
+    let (input, _) = char('\'')(input)?;
+    let (input, s) = single_quoted_body(input, '\'')?;
+
+    Ok((input, Scalar::String(s)))
+}
+
+fn parse_double_quoted_string(input: &str) -> IResult<&str, Scalar> {
+    let (input, s) = delimited(
+        char('"'),
+        opt(escaped_transform(
+            none_of("\\\""),
+            '\\',
+            alt((
+                value("\\", tag("\\")),
+                value("\"", tag("\"")),
+                value("\n", tag("n")),
+                value("\r", tag("r")),
+                value("\t", tag("t")),
+                value("\0", tag("0")),
+                value("\x0B", tag("v")),
+                value("\x08", tag("b")),
+                value("\x07", tag("a")),
+                value("\x1B", tag("e")),
+                value("\x1F", tag("z")),
+            )),
+        )),
+        char('"'),
+    )(input)?;
+
+    Ok((input, Scalar::String(s.unwrap_or_default().to_string())))
+}
+
+/// this parses:
+/// - q(foo)
+/// - q{foo}
+/// - q[foo]
+/// - q<foo>
+/// - q"foo"
+/// - q'foo'
+/// - q!foo!
+/// - q@foo@
+/// - etc
+fn parse_q_string(input: &str) -> IResult<&str, Scalar> {
+    let (input, _) = char('q')(input)?;
+    // delim is any char that is not a letter, digit, or underscore
+    let (input, start_delim) = one_of(PUNCTUATION)(input)?;
+    let end_delim = paired_quote_delimiter(start_delim);
+    let (input, s) = single_quoted_body(input, end_delim)?;
+
+    Ok((input, Scalar::String(s)))
+}
+
+fn paired_quote_delimiter(c: char) -> char {
+    match c {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        '<' => '>',
+        c => c,
+    }
+}
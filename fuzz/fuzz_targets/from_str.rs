@@ -0,0 +1,7 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: &str| {
+    let _ = serde_perl_dumper::from_str::<serde_perl_dumper::Scalar>(input);
+});
@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde::de::IgnoredAny;
+use serde_perl_dumper::{from_perl, parse, to_string};
+
+// Serializing is not stack safe, so stick to values the deserializer would accept. The first
+// trip through the serializer is allowed to normalize the value (infinities become strings,
+// scalar references are flattened), after that it has to be stable.
+fuzz_target!(|input: &str| {
+    let Ok(scalar) = parse(input) else {
+        return;
+    };
+    if from_perl::<IgnoredAny>(&scalar).is_err() {
+        return;
+    }
+
+    let output = to_string(&scalar).expect("serialize parsed value");
+    let once = parse(&output).expect("parse serialized value");
+    let output = to_string(&once).expect("serialize reparsed value");
+    let twice = parse(&output).expect("parse reserialized value");
+    assert_eq!(once, twice);
+});
@@ -0,0 +1,421 @@
+//! Math::BigInt and Math::BigFloat objects, which are read as the exact decimal number they hold
+//! rather than as the hashes the library keeps its digits in.
+
+use serde::{
+    de::{self, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{fmt, str::FromStr};
+
+use crate::error::{Error, Result};
+use crate::parser::{bytes_to_text, Reference, Scalar};
+
+/// How many decimal digits each element of a Math::BigInt::Calc array holds unless a
+/// deserializer is told otherwise.
+pub(crate) const DEFAULT_BASE_LEN: usize = 9;
+
+/// The keys a big number's hash may start with, once sorted or not.
+pub(crate) const KEYS: &[&str] = &["sign", "value", "_m", "_e", "_es", "_a", "_p"];
+
+pub(crate) fn is_big_number_class(class: &str) -> bool {
+    class == "Math::BigInt" || class == "Math::BigFloat"
+}
+
+/// The number that a Math::BigInt or Math::BigFloat object blessing `value` holds, written out in
+/// decimal. `NaN` and the infinities come out as Perl prints them.
+pub(crate) fn decimal(class: &str, value: &Scalar, base_len: usize) -> Result<String> {
+    let hash = match value {
+        Scalar::Reference(reference) => match &**reference {
+            Reference::Hash(hash) => &hash.0,
+            _ => return Err(malformed(class, "is not a hash")),
+        },
+        _ => return Err(malformed(class, "is not a hash")),
+    };
+    let field = |key: &str| hash.get(key).ok_or_else(|| malformed(class, key));
+
+    let negative = match text(field("sign")?).as_deref() {
+        Some("+") => false,
+        Some("-") => true,
+        Some("+inf") => return Ok("inf".to_owned()),
+        Some("-inf") => return Ok("-inf".to_owned()),
+        Some("NaN") => return Ok("NaN".to_owned()),
+        _ => return Err(malformed(class, "sign")),
+    };
+    let mut number = if class == "Math::BigFloat" {
+        // Older versions kept the mantissa under `value`.
+        let mantissa = hash.get("_m").or_else(|| hash.get("value"));
+        let mantissa = digits(
+            class,
+            mantissa.ok_or_else(|| malformed(class, "_m"))?,
+            base_len,
+        )?;
+        let exponent = match hash.get("_e") {
+            Some(exponent) => digits(class, exponent, base_len)?,
+            None => "0".to_owned(),
+        };
+        let negative_exponent = match hash.get("_es").map(text) {
+            None => false,
+            Some(sign) => sign.as_deref() == Some("-"),
+        };
+        scale(mantissa, &exponent, negative_exponent)
+            .ok_or_else(|| malformed(class, "_e is too large"))?
+    } else {
+        digits(class, field("value")?, base_len)?
+    };
+    if negative && number != "0" {
+        number.insert(0, '-');
+    }
+    Ok(number)
+}
+
+/// Read a Math::BigInt::Calc array, least significant chunk first, as a string of digits. Older
+/// Math::BigFloats held Math::BigInt objects rather than arrays, and those are read through.
+fn digits(class: &str, scalar: &Scalar, base_len: usize) -> Result<String> {
+    let chunks = match scalar {
+        Scalar::Reference(reference) => match &**reference {
+            Reference::Array(array) => &array.0,
+            _ => return Err(malformed(class, "has digits that are not an array")),
+        },
+        Scalar::Object(object) if object.class == "Math::BigInt" => {
+            let value = decimal(&object.class, &object.value, base_len)?;
+            return Ok(value.trim_start_matches('-').to_owned());
+        }
+        Scalar::Object(object) => return digits(class, &object.value, base_len),
+        _ => return Err(malformed(class, "has digits that are not an array")),
+    };
+    let mut number = String::with_capacity(chunks.len() * base_len);
+    for (i, chunk) in chunks.iter().rev().enumerate() {
+        let chunk = match chunk {
+            Scalar::Int(i) if *i >= 0 => i.to_string(),
+            scalar => match text(scalar) {
+                Some(s) if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) => s,
+                _ => {
+                    return Err(malformed(
+                        class,
+                        "has a chunk of digits that is not a number",
+                    ))
+                }
+            },
+        };
+        if chunk.len() > base_len {
+            return Err(malformed(
+                class,
+                "has a chunk of digits longer than the base length",
+            ));
+        }
+        // Every chunk but the most significant is padded out to the full length.
+        if i > 0 {
+            number.extend(std::iter::repeat_n('0', base_len - chunk.len()));
+        }
+        number += &chunk;
+    }
+    let trimmed = number.trim_start_matches('0');
+    Ok(if trimmed.is_empty() {
+        "0".to_owned()
+    } else {
+        trimmed.to_owned()
+    })
+}
+
+/// Write `mantissa` times ten to the power of `exponent` in decimal, or with an exponent if
+/// that would take more than a few thousand zeros.
+fn scale(mut mantissa: String, exponent: &str, negative: bool) -> Option<String> {
+    const MAX_ZEROS: usize = 4096;
+    let exponent: usize = exponent.parse().ok()?;
+    if mantissa == "0" || exponent == 0 {
+        return Some(mantissa);
+    }
+    if exponent > MAX_ZEROS + mantissa.len() {
+        let sign = if negative { '-' } else { '+' };
+        return Some(format!("{mantissa}e{sign}{exponent}"));
+    }
+    if !negative {
+        mantissa.extend(std::iter::repeat_n('0', exponent));
+    } else if exponent < mantissa.len() {
+        mantissa.insert(mantissa.len() - exponent, '.');
+    } else {
+        let zeros = "0".repeat(exponent - mantissa.len());
+        mantissa = format!("0.{zeros}{mantissa}");
+    }
+    Some(mantissa)
+}
+
+fn text(scalar: &Scalar) -> Option<String> {
+    match scalar {
+        Scalar::String(s) => Some(s.to_string()),
+        Scalar::Bytes(b) => Some(bytes_to_text(b).into_owned()),
+        Scalar::Int(i) => Some(i.to_string()),
+        _ => None,
+    }
+}
+
+fn malformed(class: &str, what: &str) -> Error {
+    <Error as de::Error>::custom(format_args!("malformed {class} object: {what}"))
+}
+
+/// An exact decimal number, however large or precise, as read from a Math::BigInt or
+/// Math::BigFloat object, from a plain number, or from a string holding one.
+///
+/// `NaN`, `inf` and `-inf` are numbers here, as they are to Math::BigInt.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigNumber(String);
+
+impl BigNumber {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the number is finite and has no fractional part.
+    pub fn is_integer(&self) -> bool {
+        let digits = self.0.strip_prefix('-').unwrap_or(&self.0);
+        !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit())
+    }
+
+    pub fn to_i128(&self) -> Option<i128> {
+        self.0.parse().ok()
+    }
+
+    pub fn to_u128(&self) -> Option<u128> {
+        self.0.parse().ok()
+    }
+
+    /// The nearest float, which may not be the number itself.
+    pub fn to_f64(&self) -> f64 {
+        self.0.parse().unwrap_or(f64::NAN)
+    }
+
+    pub fn into_string(self) -> String {
+        self.0
+    }
+}
+
+/// Accepts an optional minus sign, digits with an optional fraction, and an optional exponent,
+/// or `NaN`, `inf` or `-inf`.
+impl FromStr for BigNumber {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        if is_decimal(s) {
+            Ok(BigNumber(s.to_owned()))
+        } else {
+            Err(<Error as de::Error>::invalid_value(
+                de::Unexpected::Str(s),
+                &"a decimal number",
+            ))
+        }
+    }
+}
+
+fn is_decimal(s: &str) -> bool {
+    if matches!(s, "NaN" | "inf" | "-inf") {
+        return true;
+    }
+    let s = s.strip_prefix('-').unwrap_or(s);
+    let (number, exponent) = match s.split_once(['e', 'E']) {
+        Some((number, exponent)) => (number, Some(exponent)),
+        None => (s, None),
+    };
+    let all_digits = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    let number_ok = match number.split_once('.') {
+        Some((whole, fraction)) => all_digits(whole) && all_digits(fraction),
+        None => all_digits(number),
+    };
+    let exponent_ok = match exponent {
+        Some(exponent) => all_digits(exponent.strip_prefix(['+', '-']).unwrap_or(exponent)),
+        None => true,
+    };
+    number_ok && exponent_ok
+}
+
+impl fmt::Display for BigNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<i64> for BigNumber {
+    fn from(i: i64) -> Self {
+        BigNumber(i.to_string())
+    }
+}
+
+impl From<u64> for BigNumber {
+    fn from(u: u64) -> Self {
+        BigNumber(u.to_string())
+    }
+}
+
+impl From<i128> for BigNumber {
+    fn from(i: i128) -> Self {
+        BigNumber(i.to_string())
+    }
+}
+
+impl From<u128> for BigNumber {
+    fn from(u: u128) -> Self {
+        BigNumber(u.to_string())
+    }
+}
+
+/// Written as a string, which is how Perl holds numbers too large for it.
+impl Serialize for BigNumber {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for BigNumber {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(BigNumberVisitor)
+    }
+}
+
+struct BigNumberVisitor;
+
+impl Visitor<'_> for BigNumberVisitor {
+    type Value = BigNumber;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a number")
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> std::result::Result<BigNumber, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> std::result::Result<BigNumber, E> {
+        Ok(v.into())
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> std::result::Result<BigNumber, E> {
+        Ok(v.into())
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> std::result::Result<BigNumber, E> {
+        Ok(v.into())
+    }
+
+    /// Floats are written the shortest way that reads back as the same float.
+    fn visit_f64<E: de::Error>(self, v: f64) -> std::result::Result<BigNumber, E> {
+        if v.is_nan() {
+            Ok(BigNumber("NaN".to_owned()))
+        } else if v.is_infinite() {
+            Ok(BigNumber(if v > 0.0 { "inf" } else { "-inf" }.to_owned()))
+        } else {
+            Ok(BigNumber(v.to_string()))
+        }
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> std::result::Result<BigNumber, E> {
+        let v = v.trim_matches(|c: char| c.is_ascii_whitespace());
+        let v = v.strip_prefix('+').unwrap_or(v);
+        if is_decimal(v) {
+            Ok(BigNumber(v.to_owned()))
+        } else {
+            Err(E::invalid_value(de::Unexpected::Str(v), &self))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_perl, from_str, parser, Deserializer as PerlDeserializer};
+
+    const BIG_INT: &str = "bless( { sign => '-', value => bless( [ 456789012, 123 ], \
+                           'Math::BigInt::Calc' ) }, 'Math::BigInt' )";
+    const BIG_FLOAT: &str = "bless( { _e => [ 3 ], _es => '-', _m => [ 12345 ], sign => '+' }, \
+                             'Math::BigFloat' )";
+
+    #[test]
+    fn test_decimal() {
+        for (input, expected) in [
+            (BIG_INT, "-123456789012"),
+            (BIG_FLOAT, "12.345"),
+            (
+                "bless( { sign => '+', value => [ 0, 0, 1 ] }, 'Math::BigInt' )",
+                "1000000000000000000",
+            ),
+            (
+                "bless( { sign => '+', value => [0] }, 'Math::BigInt' )",
+                "0",
+            ),
+            (
+                "bless( { sign => '-inf', value => [0] }, 'Math::BigInt' )",
+                "-inf",
+            ),
+            (
+                "bless( { _e => [2], _es => '+', _m => [15], sign => '-' }, 'Math::BigFloat' )",
+                "-1500",
+            ),
+            (
+                "bless( { _e => [5], _es => '-', _m => [15], sign => '+' }, 'Math::BigFloat' )",
+                "0.00015",
+            ),
+        ] {
+            let Scalar::Object(object) = parser::parse(input).unwrap() else {
+                panic!("expected an object");
+            };
+            let number = decimal(&object.class, &object.value, DEFAULT_BASE_LEN).unwrap();
+            assert_eq!(number, expected, "{input}");
+        }
+
+        let input = "bless( { sign => '+', value => [ 1234567, 1 ] }, 'Math::BigInt' )";
+        let mut de = PerlDeserializer::from_str(input).big_number_base_len(7);
+        assert_eq!(String::deserialize(&mut de).unwrap(), "11234567");
+
+        let input = "bless( { sign => '+', value => [ 1234567890 ] }, 'Math::BigInt' )";
+        assert!(from_str::<String>(input).is_err());
+        assert!(from_str::<String>("bless( { sign => '+' }, 'Math::BigInt' )").is_err());
+    }
+
+    #[test]
+    fn test_deserialize() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Invoice {
+            total: BigNumber,
+            cents: i128,
+            memo: String,
+        }
+
+        let input = format!("{{ total => {BIG_FLOAT}, cents => {BIG_INT}, memo => {BIG_INT} }}");
+        let expected = Invoice {
+            total: "12.345".parse().unwrap(),
+            cents: -123456789012,
+            memo: "-123456789012".to_owned(),
+        };
+        assert_eq!(from_str::<Invoice>(&input).unwrap(), expected);
+        let scalar = parser::parse(&input).unwrap();
+        assert_eq!(from_perl::<Invoice>(&scalar).unwrap(), expected);
+
+        let values: Vec<BigNumber> =
+            from_str("[ 1, -2.5, '170141183460469231731687303715884105728' ]").unwrap();
+        assert_eq!(
+            values.iter().map(BigNumber::as_str).collect::<Vec<_>>(),
+            ["1", "-2.5", "170141183460469231731687303715884105728"]
+        );
+        assert!(values[2].is_integer() && values[2].to_i128().is_none());
+        assert_eq!(values[2].to_u128(), Some(1 << 127));
+        assert!(from_str::<BigNumber>("'12abc'").is_err());
+
+        // Other blessed hashes, and big numbers asked for as what they are, are left alone.
+        let input = "bless( { sign => 'x', value => 'y' }, 'My::Pair' )";
+        let pair: std::collections::HashMap<String, String> = from_str(input).unwrap();
+        assert_eq!(pair["sign"], "x");
+        let input =
+            "bless( { value => bless( { value => [ { sign => '+' } ] }, 'Node' ) }, 'Node' )";
+        assert_eq!(
+            from_str::<serde_json::Value>(input).unwrap(),
+            serde_json::json!({ "value": { "value": [ { "sign": "+" } ] } })
+        );
+        let blessed: crate::Blessed<std::collections::HashMap<String, serde::de::IgnoredAny>> =
+            from_str(BIG_INT).unwrap();
+        assert_eq!(blessed.class, "Math::BigInt");
+    }
+}
@@ -0,0 +1,239 @@
+//! Perl objects: references blessed into a class.
+//!
+//! [`Blessed`] keeps the class of an object alongside its value. To check the class instead of
+//! keeping it, implement [`PerlClass`] for the type and mark fields of that type
+//! `#[serde(with = "serde_perl_dumper::blessed")]`, which rejects objects of any other class.
+//!
+//! Enums whose variants are told apart by class can be internally tagged with the class, by
+//! giving the deserializer a [`class_tag`] and the enum the same `#[serde(tag = "...")]`.
+//!
+//! [`class_tag`]: crate::Deserializer::class_tag
+
+use serde::{
+    de::{self, MapAccess, Visitor},
+    ser::SerializeStruct,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use std::{fmt, marker::PhantomData};
+
+/// The struct name that tells this crate's serializer and deserializers they are dealing with a
+/// [`Blessed`], which any other format sees as a plain struct.
+pub(crate) const NAME: &str = "$serde_perl_dumper::private::Blessed";
+
+/// The fields of a [`Blessed`], in the order Perl writes them.
+pub(crate) const FIELDS: &[&str] = &["value", "class"];
+
+/// A value that was blessed into a class, written `bless( VALUE, 'CLASS' )`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Blessed<T> {
+    pub class: String,
+    pub value: T,
+}
+
+impl<T> Blessed<T> {
+    pub fn new(class: impl Into<String>, value: T) -> Self {
+        Blessed {
+            class: class.into(),
+            value,
+        }
+    }
+
+    /// Whether the value was blessed into exactly `class`. Perl's inheritance is not known here.
+    pub fn is(&self, class: &str) -> bool {
+        self.class == class
+    }
+}
+
+/// A type that Perl knows as an object of a particular class.
+pub trait PerlClass {
+    const CLASS: &'static str;
+}
+
+/// Serialize a value as an object of its [`PerlClass`].
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: PerlClass + Serialize,
+    S: Serializer,
+{
+    Blessed::new(T::CLASS, value).serialize(serializer)
+}
+
+/// Deserialize an object, failing unless it was blessed into the type's [`PerlClass`].
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: PerlClass + Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    let blessed = Blessed::<T>::deserialize(deserializer)?;
+    if !blessed.is(T::CLASS) {
+        return Err(de::Error::custom(format_args!(
+            "expected an object of class `{}`, found one of class `{}`",
+            T::CLASS,
+            blessed.class
+        )));
+    }
+    Ok(blessed.value)
+}
+
+impl<T> Serialize for Blessed<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut object = serializer.serialize_struct(NAME, FIELDS.len())?;
+        object.serialize_field(FIELDS[0], &self.value)?;
+        object.serialize_field(FIELDS[1], &self.class)?;
+        object.end()
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Blessed<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(NAME, FIELDS, BlessedVisitor(PhantomData))
+    }
+}
+
+struct BlessedVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for BlessedVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = Blessed<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a blessed reference")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(field_identifier, rename_all = "lowercase")]
+        enum Field {
+            Value,
+            Class,
+        }
+
+        let mut value = None;
+        let mut class = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                Field::Value if value.is_some() => return Err(de::Error::duplicate_field("value")),
+                Field::Value => value = Some(map.next_value()?),
+                Field::Class if class.is_some() => return Err(de::Error::duplicate_field("class")),
+                Field::Class => class = Some(map.next_value()?),
+            }
+        }
+        Ok(Blessed {
+            class: class.ok_or_else(|| de::Error::missing_field("class"))?,
+            value: value.ok_or_else(|| de::Error::missing_field("value"))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_perl, from_str, parser, to_string};
+    use serde::de::IntoDeserializer;
+    use std::borrow::Cow;
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Bug {
+        id: u32,
+    }
+
+    impl PerlClass for Bug {
+        const CLASS: &'static str = "Bugzilla::Bug";
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Attachment {
+        #[serde(with = "crate::blessed")]
+        bug: Bug,
+    }
+
+    #[test]
+    fn test_blessed() {
+        let input = "bless( { id => 1 }, 'Bugzilla::Bug' )";
+        let expected = Blessed::new("Bugzilla::Bug", Bug { id: 1 });
+        assert_eq!(from_str::<Blessed<Bug>>(input).unwrap(), expected);
+        let scalar = parser::parse(input).unwrap();
+        assert_eq!(from_perl::<Blessed<Bug>>(&scalar).unwrap(), expected);
+        assert_eq!(
+            from_str::<Blessed<Bug>>(&to_string(&expected).unwrap()).unwrap(),
+            expected
+        );
+
+        let scalar = parser::parse_borrowed("bless( do{\\(my $o = 'x')}, 'Str' )").unwrap();
+        let blessed: Blessed<Cow<str>> = from_perl(&scalar).unwrap();
+        assert_eq!(blessed, Blessed::new("Str", Cow::Borrowed("x")));
+
+        // Objects can still be read as what was blessed.
+        assert_eq!(from_str::<Bug>(input).unwrap(), Bug { id: 1 });
+        assert!(from_str::<Blessed<Bug>>("{ id => 1 }").is_err());
+        assert!(from_perl::<Blessed<Bug>>(&parser::parse("{ id => 1 }").unwrap()).is_err());
+    }
+
+    #[test]
+    fn test_class_check() {
+        let input = "{ bug => bless( { id => 2 }, 'Bugzilla::Bug' ) }";
+        let expected = Attachment { bug: Bug { id: 2 } };
+        assert_eq!(from_str::<Attachment>(input).unwrap(), expected);
+        let scalar = parser::parse(input).unwrap();
+        assert_eq!(from_perl::<Attachment>(&scalar).unwrap(), expected);
+        assert_eq!(
+            to_string(&expected).unwrap(),
+            "{bug=>bless({id=>2},'Bugzilla::Bug')}"
+        );
+
+        let input = "{ bug => bless( { id => 2 }, 'Bugzilla::User' ) }";
+        let err = from_str::<Attachment>(input).unwrap_err();
+        assert!(err.to_string().contains("`Bugzilla::User`"), "{err}");
+    }
+
+    #[test]
+    fn test_class_tag() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(tag = "class")]
+        enum Object {
+            #[serde(rename = "Bugzilla::Bug")]
+            Bug { id: u32 },
+            #[serde(rename = "Bugzilla::Attachment")]
+            Attachment { id: u32, bug_id: u32 },
+        }
+
+        let input = "[ bless( { id => 1 }, 'Bugzilla::Bug' ), \
+                       bless( { bug_id => 1, id => 7 }, 'Bugzilla::Attachment' ) ]";
+        let expected = [
+            Object::Bug { id: 1 },
+            Object::Attachment { id: 7, bug_id: 1 },
+        ];
+        let mut de = crate::Deserializer::from_str(input).class_tag("class");
+        assert_eq!(Vec::<Object>::deserialize(&mut de).unwrap(), expected);
+        de.end().unwrap();
+
+        let scalar = parser::parse(input).unwrap();
+        let de = (&scalar).into_deserializer().class_tag("class");
+        assert_eq!(Vec::<Object>::deserialize(de).unwrap(), expected);
+        let de = scalar.into_deserializer().class_tag("class");
+        assert_eq!(Vec::<Object>::deserialize(de).unwrap(), expected);
+
+        // Without a tag, or into a map, there is no class entry.
+        assert!(from_str::<Vec<Object>>(input).is_err());
+        let mut de = crate::Deserializer::from_str(input).class_tag("class");
+        let maps = Vec::<std::collections::HashMap<String, u32>>::deserialize(&mut de).unwrap();
+        assert_eq!(maps[0].len(), 1);
+    }
+}
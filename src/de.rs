@@ -1,75 +1,834 @@
+mod coerce;
+mod encoding;
+mod key;
+mod scalar;
+mod stream;
+
 use serde::{
-    de::{self, IntoDeserializer, Visitor},
+    de::{
+        self, value::BorrowedStrDeserializer, DeserializeOwned, DeserializeSeed, IntoDeserializer,
+        Visitor,
+    },
     forward_to_deserialize_any, Deserialize,
 };
-use std::borrow::Cow;
+use std::{borrow::Cow, io};
 
 use crate::error::{Error, Result};
-use crate::parser::{self, Array, Hash, Reference, Scalar};
+use crate::lexer::Token;
+use crate::parser::{self, bytes_to_text, Parser, Scalar};
+use crate::{bignum, blessed, perl_ref, registry, value};
+
+use key::KeyDeserializer;
+
+pub use encoding::Encoding;
+pub use scalar::ScalarDeserializer;
+pub use stream::{IoRead, StrRead, StreamDeserializer};
+
+/// How many hashes, arrays and scalar references may be nested inside each other before
+/// deserialization gives up, rather than overflowing the stack.
+const RECURSION_LIMIT: usize = 128;
+
+/// Settings a deserializer hands down to everything nested in it.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Options {
+    coerce: bool,
+    strict_booleans: bool,
+    class_tag: Option<&'static str>,
+    big_number_base_len: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            coerce: false,
+            strict_booleans: false,
+            class_tag: None,
+            big_number_base_len: bignum::DEFAULT_BASE_LEN,
+        }
+    }
+}
 
+/// Deserializes straight from Perl source text, one token at a time, without building a
+/// [`Scalar`] tree first.
 pub struct Deserializer<'de> {
-    scalar: Cow<'de, Scalar>,
+    parser: Parser<'de>,
+    remaining_depth: usize,
+    options: Options,
 }
 
 impl<'de> Deserializer<'de> {
-    fn new(scalar: Cow<'de, Scalar>) -> Self {
-        Deserializer { scalar }
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &'de str) -> Self {
+        Deserializer::starting_at(input, 0)
+    }
+
+    fn starting_at(input: &'de str, offset: usize) -> Self {
+        Deserializer {
+            parser: Parser::starting_at(input, offset),
+            remaining_depth: RECURSION_LIMIT,
+            options: Options::default(),
+        }
+    }
+
+    /// Apply Perl's rules for turning numbers into strings and back whenever a number, string or
+    /// character is asked for, instead of insisting on the right kind of scalar.
+    pub fn coerce(mut self, coerce: bool) -> Self {
+        self.options.coerce = coerce;
+        self
+    }
+
+    /// Only accept `0` and `1` as booleans, rather than anything Perl considers true or false.
+    pub fn strict_booleans(mut self, strict: bool) -> Self {
+        self.options.strict_booleans = strict;
+        self
+    }
+
+    /// Give each blessed hash an extra entry holding its class under `tag` when it is
+    /// deserialized as anything that asks, which is how an enum `#[serde(tag = "...")]` with the
+    /// same tag tells its variants apart by class.
+    pub fn class_tag(mut self, tag: &'static str) -> Self {
+        self.options.class_tag = Some(tag);
+        self
+    }
+
+    /// How many decimal digits each element of the arrays that Math::BigInt keeps its digits in
+    /// holds, which is `BASE_LEN` in Math::BigInt::Calc on the Perl that wrote the input. The
+    /// default of 9 is right for 64-bit Perls.
+    pub fn big_number_base_len(mut self, len: usize) -> Self {
+        self.options.big_number_base_len = len;
+        self
+    }
+
+    /// Check that the whole input has been consumed. Call this after deserializing a value to
+    /// reject trailing characters.
+    pub fn end(&mut self) -> Result<()> {
+        self.parser.end()
+    }
+
+    /// The settings to deserialize a tree parsed from this deserializer's input with.
+    pub(crate) fn options(&self) -> Options {
+        self.options
+    }
+
+    /// Parse the next value into a tree, for callers that need to see all of it before deciding
+    /// how to deserialize it.
+    pub(crate) fn parse_scalar(&mut self) -> Result<Scalar<'de>> {
+        self.parser.parse_scalar()
+    }
+
+    /// Deserialize the next value, which may be written as a `$VAR1 = ...;` statement, or return
+    /// `None` if there is nothing left but whitespace and comments.
+    fn next_statement<T>(&mut self) -> Result<Option<T>>
+    where
+        T: Deserialize<'de>,
+    {
+        if self.parser.peek()? == &Token::Eof {
+            return Ok(None);
+        }
+        self.parser.assignment()?;
+        let value = T::deserialize(&mut *self)?;
+        self.parser.statement_end();
+        Ok(Some(value))
+    }
+
+    /// Deserialize the next value, visiting hashes and arrays and looking through scalar
+    /// references, but handing any other scalar to `literal` to visit.
+    fn deserialize_literal<V, F>(&mut self, visitor: V, literal: F) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+        F: FnOnce(Scalar<'de>, V) -> Result<V::Value>,
+    {
+        let token = self.parser.next_token()?;
+        self.deserialize_token(token, visitor, literal)
+    }
+
+    /// Deserialize the value that `token` starts, like `deserialize_literal`.
+    fn deserialize_token<V, F>(
+        &mut self,
+        token: Token<'de>,
+        visitor: V,
+        literal: F,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+        F: FnOnce(Scalar<'de>, V) -> Result<V::Value>,
+    {
+        match token {
+            Token::LeftBracket => self.nested(|de| {
+                let mut seq = SeqAccess::new(de, Token::RightBracket);
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.finish()?;
+                Ok(value)
+            }),
+            Token::LeftBrace => self.nested(|de| {
+                let mut map = MapAccess::new(de);
+                let value = visitor.visit_map(&mut map)?;
+                map.finish()?;
+                Ok(value)
+            }),
+            Token::Backslash => self.nested(|de| de.deserialize_literal(visitor, literal)),
+            token if self.parser.scalar_ref_start(&token)? => self.nested(|de| {
+                let value = de.deserialize_literal(visitor, literal)?;
+                de.parser.scalar_ref_end()?;
+                Ok(value)
+            }),
+            // Objects are read as the reference that was blessed.
+            token if self.parser.object_start(&token)? => {
+                self.nested(|de| de.deserialize_object(visitor, literal))
+            }
+            token => literal(self.parser.parse_literal(token)?, visitor),
+        }
+    }
+
+    /// Deserialize what was blessed, once `bless(` has been read. A reference to a plain scalar
+    /// is read before the class is, so that booleans from the JSON modules can be visited as
+    /// booleans.
+    fn deserialize_object<V, F>(&mut self, visitor: V, literal: F) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+        F: FnOnce(Scalar<'de>, V) -> Result<V::Value>,
+    {
+        if self.parser.peek()? == &Token::LeftBrace && self.big_number_ahead()? {
+            return self.deserialize_big_number(visitor, literal);
+        }
+        let token = self.parser.next_token()?;
+        let block = token != Token::Backslash && self.parser.scalar_ref_start(&token)?;
+        if (token == Token::Backslash || block) && self.parser.peek()?.is_literal() {
+            let token = self.parser.next_token()?;
+            let mut scalar = self.parser.parse_literal(token)?;
+            if block {
+                self.parser.scalar_ref_end()?;
+            }
+            if parser::is_boolean_class(&self.parser.object_end()?) {
+                scalar = Scalar::Bool(scalar.is_true());
+            }
+            return literal(scalar, visitor);
+        }
+
+        let value = if block {
+            let value = self.nested(|de| de.deserialize_literal(visitor, literal))?;
+            self.parser.scalar_ref_end()?;
+            value
+        } else {
+            self.deserialize_token(token, visitor, literal)?
+        };
+        self.parser.object_end()?;
+        Ok(value)
+    }
+
+    /// Deserialize a scalar reference as a newtype holding what it refers to, which is what
+    /// [`PerlRef`](crate::PerlRef) asks for.
+    fn deserialize_reference<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let token = self.parser.next_token()?;
+        if token == Token::Backslash {
+            return self.nested(|de| visitor.visit_newtype_struct(&mut *de));
+        }
+        if self.parser.scalar_ref_start(&token)? {
+            return self.nested(|de| {
+                let value = visitor.visit_newtype_struct(&mut *de)?;
+                de.parser.scalar_ref_end()?;
+                Ok(value)
+            });
+        }
+        let unexpected = match token {
+            Token::LeftBracket => de::Unexpected::Seq,
+            Token::LeftBrace => de::Unexpected::Map,
+            token if token.is_literal() => {
+                let scalar = self.parser.parse_literal(token)?;
+                return Err(de::Error::invalid_type(unexpected(&scalar), &visitor));
+            }
+            _ => de::Unexpected::Other("an object"),
+        };
+        Err(de::Error::invalid_type(unexpected, &visitor))
+    }
+
+    /// Whether the object whose hash is about to be read is a Math::BigInt or Math::BigFloat,
+    /// which only its class can tell. Finding the class means reading past the hash and then
+    /// reading it again, so the hash is only read ahead for as long as it looks like a big
+    /// number's, and other objects are given up on at their first other key or nested value.
+    fn big_number_ahead(&self) -> Result<bool> {
+        let mut ahead = self.parser.clone();
+        ahead.next_token()?;
+        Ok(big_number_hash(&mut ahead, true)? && bignum::is_big_number_class(&ahead.object_end()?))
+    }
+
+    /// Whether the next value is a Math::BigInt or Math::BigFloat, or a reference to one.
+    fn big_number_next(&self) -> Result<bool> {
+        let mut ahead = self.parser.clone();
+        let mut token = ahead.next_token()?;
+        while token == Token::Backslash {
+            token = ahead.next_token()?;
+        }
+        if !ahead.object_start(&token)? || ahead.next_token()? != Token::LeftBrace {
+            return Ok(false);
+        }
+        Ok(big_number_hash(&mut ahead, true)? && bignum::is_big_number_class(&ahead.object_end()?))
+    }
+
+    /// Whether the next value is a reference to `undef`, through any number of backslashes.
+    fn undef_ref_next(&self) -> Result<bool> {
+        let mut ahead = self.parser.clone();
+        let mut token = ahead.next_token()?;
+        while token == Token::Backslash {
+            token = ahead.next_token()?;
+        }
+        Ok(token == Token::Bareword("undef"))
+    }
+
+    /// Deserialize a 128-bit integer. Big numbers are read from the digits they hold, but
+    /// without coercion any other string is left for the visitor to reject, as it is for
+    /// narrower integers.
+    fn deserialize_wide_integer<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.options.coerce {
+            self.deserialize_literal(visitor, coerce::visit_wide_integer)
+        } else if self.big_number_next()? {
+            self.deserialize_literal(visitor, visit_wide_integer)
+        } else {
+            self.deserialize_literal(visitor, visit_literal)
+        }
+    }
+
+    /// Deserialize the hash of a big number, once `bless(` has been read, as the number it holds.
+    fn deserialize_big_number<V, F>(&mut self, visitor: V, literal: F) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+        F: FnOnce(Scalar<'de>, V) -> Result<V::Value>,
+    {
+        let value = self.parser.parse_scalar()?;
+        let class = self.parser.object_end()?;
+        let number = bignum::decimal(&class, &value, self.options.big_number_base_len)?;
+        literal(Scalar::String(Cow::Owned(number)), visitor)
+    }
+
+    /// Deserialize an object as a map from `value` to the reference that was blessed and `class`
+    /// to its class, which is what [`Blessed`](crate::Blessed) asks for.
+    fn deserialize_blessed<V>(&mut self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.parser.peek()? != &Token::Bareword("bless") {
+            return Err(de::Error::invalid_type(
+                de::Unexpected::Other("an unblessed value"),
+                &visitor,
+            ));
+        }
+        let token = self.parser.next_token()?;
+        if !self.parser.object_start(&token)? {
+            return visit_literal(self.parser.parse_literal(token)?, visitor);
+        }
+        self.nested(|de| {
+            let mut object = ObjectAccess {
+                de,
+                state: ObjectState::Value,
+            };
+            let value = visitor.visit_map(&mut object)?;
+            object.finish()?;
+            Ok(value)
+        })
+    }
+
+    /// Deserialize the next value like `deserialize_any`, except that a blessed hash gets an
+    /// entry holding its class under `tag`.
+    fn deserialize_tagged<V>(&mut self, tag: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.parser.peek()? != &Token::Bareword("bless") {
+            return self.deserialize_literal(visitor, visit_literal);
+        }
+        let token = self.parser.next_token()?;
+        if !self.parser.object_start(&token)? {
+            return visit_literal(self.parser.parse_literal(token)?, visitor);
+        }
+        self.nested(|de| {
+            if de.parser.peek()? != &Token::LeftBrace {
+                return de.deserialize_object(visitor, visit_literal);
+            }
+            if de.big_number_ahead()? {
+                return de.deserialize_big_number(visitor, visit_literal);
+            }
+            de.parser.next_token()?;
+            de.nested(|de| {
+                let mut map = MapAccess::new(de);
+                map.class_entry = ClassEntry::Tag(tag);
+                let value = visitor.visit_map(&mut map)?;
+                map.finish()?;
+                Ok(value)
+            })
+        })
+    }
+
+    /// Run `f` one level deeper in the data, failing if that is too deep.
+    fn nested<T>(&mut self, f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        self.remaining_depth = self
+            .remaining_depth
+            .checked_sub(1)
+            .ok_or(Error::RecursionLimitExceeded)?;
+        let result = f(self);
+        self.remaining_depth += 1;
+        result
     }
 }
 
-pub fn from_perl<'de, T>(scalar: &'de Scalar) -> Result<T>
+pub fn from_perl<'de, T>(scalar: &'de Scalar<'de>) -> Result<T>
 where
     T: Deserialize<'de>,
 {
-    let deserializer = Deserializer::new(Cow::Borrowed(scalar));
+    let deserializer = ScalarDeserializer::new(Cow::Borrowed(scalar));
     T::deserialize(deserializer)
 }
 
-pub fn from_str<'de, T>(scalar: &'de str) -> Result<T>
+pub fn from_str<'de, T>(input: &'de str) -> Result<T>
 where
     T: Deserialize<'de>,
 {
-    let scalar = parser::parse(scalar)?;
-    let deserializer = Deserializer::new(Cow::Owned(scalar));
-    T::deserialize(deserializer)
+    let mut deserializer = Deserializer::from_str(input);
+    let value = T::deserialize(&mut deserializer)?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Visit a scalar that is not a reference as whatever it is.
+fn visit_literal<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match scalar {
+        Scalar::Int(i) => visitor.visit_i64(i),
+        Scalar::Float(f) => visitor.visit_f64(f),
+        Scalar::String(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+        Scalar::String(Cow::Owned(s)) => visitor.visit_string(s),
+        Scalar::Bytes(Cow::Borrowed(b)) => visitor.visit_borrowed_bytes(b),
+        Scalar::Bytes(Cow::Owned(b)) => visitor.visit_byte_buf(b),
+        Scalar::Bool(b) => visitor.visit_bool(b),
+        // Literals are never references.
+        Scalar::Undefined | Scalar::Reference(_) | Scalar::Object(_) => visitor.visit_unit(),
+    }
 }
 
-impl<'de> de::Deserializer<'de> for Deserializer<'de> {
+/// Visit a scalar that is not a reference, reading byte strings as text.
+fn visit_text<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match scalar {
+        Scalar::Bytes(Cow::Borrowed(b)) => match bytes_to_text(b) {
+            Cow::Borrowed(s) => visitor.visit_borrowed_str(s),
+            Cow::Owned(s) => visitor.visit_string(s),
+        },
+        Scalar::Bytes(Cow::Owned(b)) => match String::from_utf8(b) {
+            Ok(s) => visitor.visit_string(s),
+            Err(err) => visitor.visit_string(bytes_to_text(err.as_bytes()).into_owned()),
+        },
+        scalar => visit_literal(scalar, visitor),
+    }
+}
+
+/// Visit a scalar that is not a reference, giving strings as their UTF-8 bytes.
+fn visit_bytes<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match scalar {
+        Scalar::String(Cow::Borrowed(s)) => visitor.visit_borrowed_bytes(s.as_bytes()),
+        Scalar::String(Cow::Owned(s)) => visitor.visit_byte_buf(s.into_bytes()),
+        scalar => visit_literal(scalar, visitor),
+    }
+}
+
+/// Visit a scalar that is not a reference as a boolean by Perl's rules: `undef`, `''`, `'0'` and
+/// numeric zero are false, and everything else is true.
+fn visit_truthiness<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match scalar {
+        Scalar::Reference(_) | Scalar::Object(_) => visit_literal(scalar, visitor),
+        scalar => visitor.visit_bool(scalar.is_true()),
+    }
+}
+
+/// A string holding an integer too wide for Perl, which Data::Dumper writes in quotes.
+pub(super) fn wide_integer(scalar: &Scalar) -> Option<std::result::Result<i128, u128>> {
+    let digits = match scalar {
+        Scalar::String(s) => &**s,
+        _ => return None,
+    };
+    match digits.parse() {
+        Ok(i) => Some(Ok(i)),
+        Err(_) => digits.parse().ok().map(Err),
+    }
+}
+
+/// Visit a scalar that is not a reference as whatever it is, except that a string of digits, as a
+/// big number is read as, is visited as the 128-bit integer it holds.
+fn visit_wide_integer<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match wide_integer(&scalar) {
+        Some(Ok(i)) => visitor.visit_i128(i),
+        Some(Err(u)) => visitor.visit_u128(u),
+        None => visit_literal(scalar, visitor),
+    }
+}
+
+/// Visit a scalar that is not a reference as a boolean, accepting only `0` and `1`, quoted or not.
+fn visit_strict_bool<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match scalar {
+        Scalar::Int(0) => visitor.visit_bool(false),
+        Scalar::Int(1) => visitor.visit_bool(true),
+        Scalar::Bool(b) => visitor.visit_bool(b),
+        Scalar::String(s) if s == "0" || s == "1" => visitor.visit_bool(s == "1"),
+        Scalar::Bytes(b) if *b == *b"0" || *b == *b"1" => visitor.visit_bool(*b == *b"1"),
+        scalar => Err(de::Error::invalid_value(unexpected(&scalar), &"0 or 1")),
+    }
+}
+
+/// Describe a scalar that is not a reference for an error message.
+fn unexpected<'a>(scalar: &'a Scalar) -> de::Unexpected<'a> {
+    match scalar {
+        Scalar::Int(i) => de::Unexpected::Signed(*i),
+        Scalar::Float(f) => de::Unexpected::Float(*f),
+        Scalar::String(s) => de::Unexpected::Str(s),
+        Scalar::Bytes(b) => de::Unexpected::Bytes(b),
+        Scalar::Bool(b) => de::Unexpected::Bool(*b),
+        Scalar::Undefined | Scalar::Reference(_) | Scalar::Object(_) => de::Unexpected::Unit,
+    }
+}
+
+/// Visit a scalar that is not a reference as the name of a unit variant.
+fn visit_unit_variant<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match scalar {
+        Scalar::String(Cow::Borrowed(s)) => visitor.visit_enum(BorrowedStrDeserializer::new(s)),
+        Scalar::String(Cow::Owned(s)) => visitor.visit_enum(s.into_deserializer()),
+        Scalar::Bytes(b) => visitor.visit_enum(bytes_to_text(&b).into_owned().into_deserializer()),
+        scalar => visit_literal(scalar, visitor),
+    }
+}
+
+/// Deserialize a hash key, lending it out if it is borrowed.
+fn deserialize_key<'de, K>(seed: K, key: Cow<'de, str>) -> Result<K::Value>
+where
+    K: DeserializeSeed<'de>,
+{
+    seed.deserialize(KeyDeserializer::new(key))
+}
+
+/// Implement `deserialize_*` methods that visit literals with one function normally, and another
+/// when coercing.
+macro_rules! deserialize_coerced {
+    ($($method:ident => $coerced:path, $strict:path;)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                if self.options.coerce {
+                    self.deserialize_literal(visitor, $coerced)
+                } else {
+                    self.deserialize_literal(visitor, $strict)
+                }
+            }
+        )*
+    };
+}
+use deserialize_coerced;
+
+/// Read past a hash that could be a big number's once its `{` has been: one with only the keys
+/// a big number has, holding literals, arrays of literals (blessed into Math::BigInt::Calc or
+/// not), or, if `objects` allows, the Math::BigInt objects older Math::BigFloats hold. Says
+/// whether it was one, stopping at the first thing that is not.
+fn big_number_hash(parser: &mut Parser<'_>, objects: bool) -> Result<bool> {
+    if parser.peek()? == &Token::RightBrace {
+        return Ok(false);
+    }
+    loop {
+        if !bignum::KEYS.contains(&&*parser.parse_key()?) {
+            return Ok(false);
+        }
+        let looks_like_one = match parser.next_token()? {
+            token if token.is_literal() => {
+                parser.parse_literal(token)?;
+                true
+            }
+            Token::LeftBracket => literal_array(parser)?,
+            token if parser.object_start(&token)? => match parser.next_token()? {
+                Token::LeftBracket if literal_array(parser)? => {
+                    parser.object_end()?;
+                    true
+                }
+                Token::LeftBrace if objects => {
+                    big_number_hash(parser, false)? && parser.object_end()? == "Math::BigInt"
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        if !looks_like_one {
+            return Ok(false);
+        }
+        if !parser.list_separator(Token::RightBrace)? {
+            return Ok(true);
+        }
+    }
+}
+
+/// Read past an array once its `[` has been, saying whether it held nothing but literals.
+fn literal_array(parser: &mut Parser<'_>) -> Result<bool> {
+    if parser.peek()? == &Token::RightBracket {
+        parser.next_token()?;
+        return Ok(true);
+    }
+    loop {
+        let token = parser.next_token()?;
+        if !token.is_literal() {
+            return Ok(false);
+        }
+        parser.parse_literal(token)?;
+        if !parser.list_separator(Token::RightBracket)? {
+            return Ok(true);
+        }
+    }
+}
+
+fn single_key_expected() -> Error {
+    de::Error::invalid_value(de::Unexpected::Map, &"a hash with a single key")
+}
+
+/// Deserialize from UTF-8 bytes, borrowing strings from them where possible.
+pub fn from_slice<'de, T>(input: &'de [u8]) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    from_str(std::str::from_utf8(input)?)
+}
+
+/// Deserialize from bytes in the given encoding.
+pub fn from_slice_with_encoding<T>(input: &[u8], encoding: Encoding) -> Result<T>
+where
+    T: DeserializeOwned,
+{
+    from_str(&encoding.decode(input)?)
+}
+
+/// Deserialize from everything `reader` produces, which must be UTF-8.
+pub fn from_reader<R, T>(reader: R) -> Result<T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    from_reader_with_encoding(reader, Encoding::Utf8)
+}
+
+/// Deserialize from everything `reader` produces, in the given encoding.
+pub fn from_reader_with_encoding<R, T>(mut reader: R, encoding: Encoding) -> Result<T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    let mut input = Vec::new();
+    reader.read_to_end(&mut input)?;
+    from_slice_with_encoding(&input, encoding)
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
     type Error = Error;
 
     fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
     where
         V: Visitor<'de>,
     {
-        let scalar = self.scalar.into_owned();
-        match scalar {
-            Scalar::Undefined => visitor.visit_unit(),
-            Scalar::Int(i) => visitor.visit_i64(i),
-            Scalar::Float(f) => visitor.visit_f64(f),
-            Scalar::String(s) => visitor.visit_string(s),
-            Scalar::Reference(r) => match *r {
-                Reference::Hash(h) => {
-                    let Hash(h) = *h;
-                    let mut map = serde::de::value::MapDeserializer::new(h.into_iter());
-                    visitor.visit_map(&mut map)
-                }
-                Reference::Array(a) => {
-                    let Array(a) = *a;
-                    let mut seq = serde::de::value::SeqDeserializer::new(a.into_iter());
-                    visitor.visit_seq(&mut seq)
+        match self.options.class_tag {
+            Some(tag) => self.deserialize_tagged(tag, visitor),
+            None => self.deserialize_literal(visitor, visit_literal),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == perl_ref::NAME {
+            self.deserialize_reference(visitor)
+        } else if name == registry::NAME {
+            let options = self.options;
+            registry::hand_over(self, options, visitor)
+        } else if name == value::de::NAME {
+            // A tree is built from a tree, so parsing one first costs nothing extra.
+            let scalar = self.parser.parse_scalar()?;
+            ScalarDeserializer::nested(Cow::Owned(scalar), self.remaining_depth, self.options)
+                .deserialize_tree(visitor)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    // Maps and structs read blessed hashes without a class tag, which is only for the enums and
+    // such that buffer whatever they are given.
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_literal(visitor, visit_literal)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == blessed::NAME {
+            self.deserialize_blessed(visitor)
+        } else {
+            self.deserialize_literal(visitor, visit_literal)
+        }
+    }
+
+    /// `undef` is `None` and anything else is `Some`. A reference to `undef` is `None` too, but
+    /// any other reference is left whole for `Some`, so that a [`PerlRef`](crate::PerlRef) in
+    /// it still sees the reference.
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.parser.peek()? {
+            Token::Backslash => {
+                if self.undef_ref_next()? {
+                    self.parser.skip_scalar()?;
+                    visitor.visit_none()
+                } else {
+                    visitor.visit_some(self)
                 }
-                Reference::Scalar(s) => {
-                    let deserializer = s.into_deserializer();
-                    deserializer.deserialize_any(visitor)
+            }
+            Token::Bareword("undef") => {
+                self.parser.next_token()?;
+                // Unless the fat comma quotes it.
+                if self.parser.peek()? == &Token::FatComma {
+                    visitor.visit_some(BorrowedStrDeserializer::new("undef"))
+                } else {
+                    visitor.visit_none()
                 }
-            },
+            }
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    /// Unit variants are strings, and the others hashes with the variant name as their only key,
+    /// the way the serializer writes them.
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.parser.peek()? {
+            Token::Backslash => {
+                self.parser.next_token()?;
+                self.nested(|de| de.deserialize_enum(name, variants, visitor))
+            }
+            Token::LeftBrace => {
+                self.parser.next_token()?;
+                self.nested(|de| {
+                    let value = visitor.visit_enum(EnumAccess { de: &mut *de })?;
+                    if de.parser.list_separator(Token::RightBrace)? {
+                        return Err(single_key_expected());
+                    }
+                    Ok(value)
+                })
+            }
+            _ => self.deserialize_literal(visitor, visit_unit_variant),
         }
     }
 
+    /// Perl has no booleans, so anything it considers true or false will do. JSON modules write
+    /// them as `\0` and `\1`, or objects blessed into `JSON::PP::Boolean`, which are looked
+    /// through to the number inside.
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.options.strict_booleans {
+            self.deserialize_literal(visitor, visit_strict_bool)
+        } else {
+            self.deserialize_literal(visitor, visit_truthiness)
+        }
+    }
+
+    deserialize_coerced! {
+        deserialize_i8 => coerce::visit_integer, visit_literal;
+        deserialize_i16 => coerce::visit_integer, visit_literal;
+        deserialize_i32 => coerce::visit_integer, visit_literal;
+        deserialize_i64 => coerce::visit_integer, visit_literal;
+        deserialize_u8 => coerce::visit_integer, visit_literal;
+        deserialize_u16 => coerce::visit_integer, visit_literal;
+        deserialize_u32 => coerce::visit_integer, visit_literal;
+        deserialize_u64 => coerce::visit_integer, visit_literal;
+        deserialize_f32 => coerce::visit_float, visit_literal;
+        deserialize_f64 => coerce::visit_float, visit_literal;
+        deserialize_char => coerce::visit_string, visit_literal;
+        deserialize_str => coerce::visit_string, visit_text;
+        deserialize_string => coerce::visit_string, visit_text;
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_wide_integer(visitor)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_wide_integer(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_literal(visitor, visit_bytes)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_literal(visitor, visit_bytes)
+    }
+
+    // Skipping a value neither builds it nor recurses, so it needs no depth limit.
+    fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.parser.skip_scalar()?;
+        visitor.visit_unit()
+    }
+
     forward_to_deserialize_any! {
-        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-        bytes byte_buf option unit unit_struct newtype_struct seq tuple
-        tuple_struct map struct enum identifier ignored_any
+        unit unit_struct seq tuple tuple_struct identifier
     }
 
     fn is_human_readable(&self) -> bool {
@@ -77,79 +836,565 @@ impl<'de> de::Deserializer<'de> for Deserializer<'de> {
     }
 }
 
-impl<'de> IntoDeserializer<'de, Error> for &'de Scalar {
-    type Deserializer = Deserializer<'de>;
+/// Reads the elements of an array whose `[` has already been consumed.
+struct SeqAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    close: Token<'static>,
+    first: bool,
+    done: bool,
+}
+
+impl<'a, 'de> SeqAccess<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>, close: Token<'static>) -> Self {
+        SeqAccess {
+            de,
+            close,
+            first: true,
+            done: false,
+        }
+    }
+
+    /// Move on to the next element, returning `false` at the end of the list.
+    fn advance(&mut self) -> Result<bool> {
+        if self.done {
+            return Ok(false);
+        }
+        let more = if self.first {
+            self.first = false;
+            if self.de.parser.peek()? == &self.close {
+                self.de.parser.next_token()?;
+                false
+            } else {
+                true
+            }
+        } else {
+            self.de.parser.list_separator(self.close.clone())?
+        };
+        self.done = !more;
+        Ok(more)
+    }
+
+    /// Skip any elements the visitor didn't ask for, as the tree deserializer ignores them too.
+    fn finish(&mut self) -> Result<()> {
+        while self.advance()? {
+            self.de.parser.skip_scalar()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for SeqAccess<'_, 'de> {
+    type Error = Error;
 
-    fn into_deserializer(self) -> Self::Deserializer {
-        Deserializer::new(Cow::Borrowed(self))
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        if self.advance()? {
+            seed.deserialize(&mut *self.de).map(Some)
+        } else {
+            Ok(None)
+        }
     }
 }
 
-impl<'de> IntoDeserializer<'de, Error> for Scalar {
-    type Deserializer = Deserializer<'de>;
+/// Reads the entries of a hash whose `{` has already been consumed.
+struct MapAccess<'a, 'de> {
+    entries: SeqAccess<'a, 'de>,
+    class_entry: ClassEntry<'de>,
+}
+
+/// The entry holding the class of a blessed hash, which follows the others because the class
+/// follows the hash.
+enum ClassEntry<'de> {
+    None,
+    /// Still to come, under this key, once the end of the object gives the class.
+    Tag(&'static str),
+    /// The key has been visited and this class is the value.
+    Class(Cow<'de, str>),
+}
 
-    fn into_deserializer(self) -> Self::Deserializer {
-        Deserializer::new(Cow::Owned(self))
+impl<'a, 'de> MapAccess<'a, 'de> {
+    fn new(de: &'a mut Deserializer<'de>) -> Self {
+        MapAccess {
+            entries: SeqAccess::new(de, Token::RightBrace),
+            class_entry: ClassEntry::None,
+        }
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        while self.entries.advance()? {
+            self.entries.de.parser.parse_key()?;
+            self.entries.de.parser.skip_scalar()?;
+        }
+        if let ClassEntry::Tag(_) = self.class_entry {
+            self.entries.de.parser.object_end()?;
+            self.class_entry = ClassEntry::None;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> de::MapAccess<'de> for MapAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        if self.entries.advance()? {
+            return deserialize_key(seed, self.entries.de.parser.parse_key()?).map(Some);
+        }
+        match self.class_entry {
+            ClassEntry::Tag(tag) => {
+                self.class_entry = ClassEntry::Class(self.entries.de.parser.object_end()?);
+                deserialize_key(seed, Cow::Borrowed(tag)).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match std::mem::replace(&mut self.class_entry, ClassEntry::None) {
+            ClassEntry::Class(class) => deserialize_key(seed, class),
+            class_entry => {
+                self.class_entry = class_entry;
+                seed.deserialize(&mut *self.entries.de)
+            }
+        }
+    }
+}
+
+/// Reads an object as a map from `value` to the reference that was blessed, then `class` to the
+/// class, once the `bless(` has been consumed.
+struct ObjectAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+    state: ObjectState<'de>,
+}
+
+enum ObjectState<'de> {
+    Value,
+    Class(Cow<'de, str>),
+    Done,
+}
+
+impl ObjectAccess<'_, '_> {
+    fn finish(&mut self) -> Result<()> {
+        if let ObjectState::Value = self.state {
+            self.de.parser.skip_scalar()?;
+            self.de.parser.object_end()?;
+            self.state = ObjectState::Done;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> de::MapAccess<'de> for ObjectAccess<'_, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        let key = match self.state {
+            ObjectState::Value => blessed::FIELDS[0],
+            ObjectState::Class(_) => blessed::FIELDS[1],
+            ObjectState::Done => return Ok(None),
+        };
+        deserialize_key(seed, Cow::Borrowed(key)).map(Some)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        match std::mem::replace(&mut self.state, ObjectState::Done) {
+            ObjectState::Value => {
+                let value = seed.deserialize(&mut *self.de)?;
+                self.state = ObjectState::Class(self.de.parser.object_end()?);
+                Ok(value)
+            }
+            ObjectState::Class(class) => deserialize_key(seed, class),
+            ObjectState::Done => Err(de::Error::custom("no entry left in the object")),
+        }
+    }
+}
+
+/// Reads the variant of an enum written as a hash whose `{` has already been consumed.
+struct EnumAccess<'a, 'de> {
+    de: &'a mut Deserializer<'de>,
+}
+
+impl<'a, 'de> de::EnumAccess<'de> for EnumAccess<'a, 'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let variant = deserialize_key(seed, self.de.parser.parse_key()?)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for EnumAccess<'_, 'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Deserialize::deserialize(self.de)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self.de)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self.de, visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self.de, visitor)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::parser::{Hash, Reference};
-
     use super::*;
-    use serde::Deserialize;
+    use serde::{Deserialize, Serialize};
+    use std::collections::HashMap;
+
+    /// Arbitrarily deep nesting of arrays.
+    #[derive(Deserialize, Debug)]
+    #[serde(transparent)]
+    struct Nested(#[allow(dead_code)] Vec<Nested>);
+
+    #[test]
+    fn test_recursion_limit() {
+        let input = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        let err = from_str::<Nested>(&input).unwrap_err();
+        assert!(matches!(err, Error::RecursionLimitExceeded));
+
+        let input = format!("{}1", "\\".repeat(100_000));
+        let err = from_str::<Nested>(&input).unwrap_err();
+        assert!(matches!(err, Error::RecursionLimitExceeded));
+
+        let input = format!(
+            "{}{}",
+            "[".repeat(RECURSION_LIMIT),
+            "]".repeat(RECURSION_LIMIT)
+        );
+        from_str::<Nested>(&input).unwrap();
+    }
 
     #[test]
-    fn test_deserialize() {
+    fn test_deserialize_borrowed() {
         #[derive(Deserialize, Debug, PartialEq)]
-        struct Test {
-            a: i32,
-            b: String,
+        struct Test<'a> {
+            name: &'a str,
+            #[serde(borrow)]
+            escaped: Cow<'a, str>,
+            #[serde(borrow)]
+            tags: HashMap<&'a str, &'a str>,
         }
 
-        let scalar = Scalar::Reference(Box::new(Reference::Hash(Box::new(Hash(
-            vec![
-                (String::from("a"), Scalar::Int(42)),
-                (String::from("b"), Scalar::String(String::from("hello"))),
-            ]
-            .into_iter()
-            .collect::<std::collections::HashMap<String, Scalar>>(),
-        )))));
+        let input = r#"{ name => 'hello', escaped => "a\tb", tags => { 'c' => q{d} } }"#;
+        let test: Test = from_str(input).unwrap();
+        assert_eq!(test.name, "hello");
+        assert!(matches!(test.escaped, Cow::Owned(ref s) if s == "a\tb"));
+        assert_eq!(test.tags["c"], "d");
+
+        assert!(from_str::<&str>(r"'it\'s'").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_perl() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Flag {
+            name: String,
+            status: char,
+        }
 
-        let test: Test = from_perl(&scalar).unwrap();
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Bug {
+            id: u32,
+            summary: String,
+            flags: Vec<Flag>,
+            cc: Vec<i32>,
+        }
+
+        let input = r#"
+            {
+                'id' => 42,
+                'summary' => "Crash on \"save\"",
+                'unknown' => [ { deeply => [ 'nested' ] } ],
+                'flags' => [ { name => 'review', status => '?', extra => \1 } ],
+                'cc' => [ 1, 2, 3, ],
+            }
+        "#;
+        let bug: Bug = from_str(input).unwrap();
         assert_eq!(
-            test,
-            Test {
-                a: 42,
-                b: "hello".to_string()
+            bug,
+            Bug {
+                id: 42,
+                summary: "Crash on \"save\"".to_string(),
+                flags: vec![Flag {
+                    name: "review".to_string(),
+                    status: '?',
+                }],
+                cc: vec![1, 2, 3],
             }
         );
     }
 
-    // let's parse some perl
     #[test]
-    fn test_deserialize_perl() {
+    fn test_from_bytes() {
+        let name: &str = from_slice(b"'caf\xc3\xa9'").unwrap();
+        assert_eq!(name, "café");
+        assert!(matches!(
+            from_slice::<&str>(b"'caf\xe9'"),
+            Err(Error::Utf8(_))
+        ));
+
+        let input: &[u8] = b"{ name => 'caf\xe9' }";
+        let map: HashMap<String, String> =
+            from_slice_with_encoding(input, Encoding::Latin1).unwrap();
+        assert_eq!(map["name"], "café");
+        let map: HashMap<String, String> =
+            from_reader_with_encoding(input, Encoding::Utf8Lossy).unwrap();
+        assert_eq!(map["name"], "caf\u{fffd}");
+        assert!(from_reader::<_, HashMap<String, String>>(input).is_err());
+    }
+
+    #[test]
+    fn test_byte_strings() {
+        let bytes: serde_bytes::ByteBuf = from_str(r#""\x00\xffab""#).unwrap();
+        assert_eq!(bytes.as_slice(), b"\x00\xffab");
+        let bytes: &[u8] = from_str("'plain'").unwrap();
+        assert_eq!(bytes, b"plain");
+
+        // Encoded text reads as UTF-8, anything else as Latin-1.
+        let text: Vec<String> = from_str(r#"[ "caf\303\251", "caf\xe9" ]"#).unwrap();
+        assert_eq!(text, vec!["café", "café"]);
+        let keys: HashMap<String, i32> = from_str(r#"{ "\xe9" => 1 }"#).unwrap();
+        assert_eq!(keys["é"], 1);
+
+        let input = crate::to_string(&serde_bytes::Bytes::new(b"\x89PNG\r\n")).unwrap();
+        let bytes: serde_bytes::ByteBuf = from_str(&input).unwrap();
+        assert_eq!(bytes.as_slice(), b"\x89PNG\r\n");
+    }
+
+    #[test]
+    fn test_option() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Bug {
+            assignee: Option<String>,
+            votes: Option<i32>,
+            flags: Option<Vec<i32>>,
+            estimate: Option<f64>,
+        }
+
+        let bug: Bug =
+            from_str("{ assignee => undef, votes => 5, flags => [1], estimate => \\1.5 }").unwrap();
+        assert_eq!(
+            bug,
+            Bug {
+                assignee: None,
+                votes: Some(5),
+                flags: Some(vec![1]),
+                estimate: Some(1.5),
+            }
+        );
+
+        let values: Vec<Option<i32>> = from_str("[ undef, 1, \\undef, \\\\2 ]").unwrap();
+        assert_eq!(values, vec![None, Some(1), None, Some(2)]);
+        let values: Vec<Option<&str>> = from_str("[ undef => undef ]").unwrap();
+        assert_eq!(values, vec![Some("undef"), None]);
+    }
+
+    #[test]
+    fn test_coerce() {
         #[derive(Deserialize, Debug, PartialEq)]
-        struct Test {
-            a: i32,
-            b: String,
-            topic: String,
-            c: Vec<i32>,
+        struct Bug {
+            id: i32,
+            votes: Option<u64>,
+            estimate: f64,
+            version: String,
+            severity: char,
         }
 
-        let scalar =
-            parser::parse(r#"{a => 42, 'b' => 'hello', "topic" => "\nworld", 'c' => [1, 2, 3]}"#)
-                .unwrap();
-        let test: Test = from_perl(&scalar).unwrap();
+        let input =
+            "{ id => '42', votes => \\' 7 ', estimate => 3, version => 1.5, severity => 2 }";
+        assert!(from_str::<Bug>(input).is_err());
+        let mut de = Deserializer::from_str(input).coerce(true);
+        let bug = Bug::deserialize(&mut de).unwrap();
+        de.end().unwrap();
         assert_eq!(
-            test,
-            Test {
-                a: 42,
-                b: "hello".to_string(),
-                topic: "\nworld".to_string(),
-                c: vec![1, 2, 3]
+            bug,
+            Bug {
+                id: 42,
+                votes: Some(7),
+                estimate: 3.0,
+                version: "1.5".to_owned(),
+                severity: '2',
             }
         );
+
+        let mut de = Deserializer::from_str("[ '1e3', 2.0, 0.1 ]").coerce(true);
+        let values: (u16, i8, String) = Deserialize::deserialize(&mut de).unwrap();
+        assert_eq!(values, (1000, 2, "0.1".to_owned()));
+
+        let mut de = Deserializer::from_str("'42abc'").coerce(true);
+        assert!(i32::deserialize(&mut de).is_err());
+        let mut de = Deserializer::from_str("2.5").coerce(true);
+        assert!(i32::deserialize(&mut de).is_err());
+        let mut de = Deserializer::from_str("undef").coerce(true);
+        assert!(i32::deserialize(&mut de).is_err());
+
+        // Quoted 128-bit integers are only read with coercion too.
+        let input = "[ 42, '-42', '170141183460469231731687303715884105727' ]";
+        assert!(from_str::<Vec<i128>>(input).is_err());
+        assert!(from_str::<(u128,)>("['42']").is_err());
+        let scalar = parser::parse(input).unwrap();
+        assert!(from_perl::<Vec<i128>>(&scalar).is_err());
+        assert_eq!(from_str::<(i128,)>("[42]").unwrap(), (42,));
+        let mut de = Deserializer::from_str(input).coerce(true);
+        let values = Vec::<i128>::deserialize(&mut de).unwrap();
+        assert_eq!(values, [42, -42, i128::MAX]);
+        let de = ScalarDeserializer::new(Cow::Borrowed(&scalar)).coerce(true);
+        assert_eq!(Vec::<i128>::deserialize(de).unwrap(), [42, -42, i128::MAX]);
+    }
+
+    #[test]
+    fn test_bool() {
+        let input = "[ 1, 0, '', '0', '0.0', 'no', undef, 0.0, -1, \\1, \\0, \\\\'' ]";
+        let values: Vec<bool> = from_str(input).unwrap();
+        assert_eq!(
+            values,
+            [true, false, false, false, true, true, false, false, true, true, false, false]
+        );
+
+        let input = "[ bless( do{\\(my $o = 1)}, 'JSON::PP::Boolean' ), \
+                       bless( do{\\(my $o = 0)}, 'JSON::PP::Boolean' ) ]";
+        let values: Vec<bool> = from_str(input).unwrap();
+        assert_eq!(values, [true, false]);
+
+        let mut de = Deserializer::from_str("[ 1, '0', \\1 ]").strict_booleans(true);
+        let values = Vec::<bool>::deserialize(&mut de).unwrap();
+        assert_eq!(values, [true, false, true]);
+        for input in ["''", "undef", "2", "'yes'", "1.0"] {
+            let mut de = Deserializer::from_str(input).strict_booleans(true);
+            assert!(bool::deserialize(&mut de).is_err(), "{input}");
+        }
+        assert!(from_str::<bool>("[]").is_err());
+    }
+
+    #[test]
+    fn test_json_booleans() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        #[serde(untagged)]
+        enum Value {
+            Bool(bool),
+            Int(i64),
+            Str(String),
+        }
+
+        let input = "[ bless( do{\\(my $o = 1)}, 'JSON::PP::Boolean' ), \
+                       bless( \\0, 'JSON::XS::Boolean' ), \
+                       bless( do{\\(my $o = 1)}, 'My::Counter' ), 1, '1' ]";
+        let values: Vec<Value> = from_str(input).unwrap();
+        assert_eq!(
+            values,
+            [
+                Value::Bool(true),
+                Value::Bool(false),
+                Value::Int(1),
+                Value::Int(1),
+                Value::Str("1".to_owned()),
+            ]
+        );
+        let scalar = parser::parse(input).unwrap();
+        assert_eq!(from_perl::<Vec<Value>>(&scalar).unwrap(), values);
+        let mut de = Deserializer::from_str(input).class_tag("class");
+        assert_eq!(Vec::<Value>::deserialize(&mut de).unwrap(), values);
+
+        let input = "[ bless( \\1, 'JSON::PP::Boolean' ), bless( \\'', 'JSON::PP::Boolean' ) ]";
+        let mut de = Deserializer::from_str(input).strict_booleans(true);
+        assert_eq!(Vec::<bool>::deserialize(&mut de).unwrap(), [true, false]);
+        let mut de = Deserializer::from_str(input).coerce(true);
+        assert_eq!(Vec::<String>::deserialize(&mut de).unwrap(), ["1", "0"]);
+        assert!(from_str::<Vec<i32>>(input).is_err());
+    }
+
+    #[test]
+    fn test_enum() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        enum E {
+            Unit,
+            Newtype(u32),
+            Tuple(u32, u32),
+            Struct { a: u32 },
+            Undefined { val: Option<u32> },
+            Nested(Box<E>),
+        }
+
+        let values = vec![
+            E::Unit,
+            E::Newtype(1),
+            E::Tuple(1, 2),
+            E::Struct { a: 1 },
+            E::Undefined { val: None },
+            E::Nested(Box::new(E::Tuple(3, 4))),
+        ];
+        let input = crate::to_string(&values).unwrap();
+        assert_eq!(from_str::<Vec<E>>(&input).unwrap(), values);
+
+        let input =
+            r#"[ "Unit", \'Unit', { Unit => undef }, { 'Newtype' => \1 }, {Tuple=>[1,2],} ]"#;
+        assert_eq!(
+            from_str::<Vec<E>>(input).unwrap(),
+            vec![E::Unit, E::Unit, E::Unit, E::Newtype(1), E::Tuple(1, 2)]
+        );
+
+        assert!(from_str::<E>("{ Newtype => 1, Unit => undef }").is_err());
+        assert!(from_str::<E>("{}").is_err());
+        assert!(from_str::<E>("'Unknown'").is_err());
+        assert!(from_str::<E>("1").is_err());
+    }
+
+    #[test]
+    fn test_unvisited_elements_are_skipped() {
+        let pair: (i32, i32) = from_str("[1, 2, [3, 4], { five => 5 }]").unwrap();
+        assert_eq!(pair, (1, 2));
+    }
+
+    #[test]
+    fn test_trailing_characters() {
+        assert!(matches!(
+            from_str::<Vec<i32>>("[1, 2] 3"),
+            Err(Error::Syntax { .. })
+        ));
+        assert!(matches!(
+            from_str::<HashMap<String, i32>>("{ a => 1"),
+            Err(Error::Eof { .. })
+        ));
+    }
+
+    #[test]
+    fn test_ignored_any_is_not_depth_limited() {
+        let input = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        from_str::<serde::de::IgnoredAny>(&input).unwrap();
     }
 }
@@ -0,0 +1,197 @@
+//! Perl's rules for reading numbers out of strings and writing them back, for deserializers that
+//! have been asked to coerce scalars.
+
+use serde::de::{self, Visitor};
+
+use super::{visit_literal, visit_text, wide_integer};
+use crate::error::{Error, Result};
+use crate::parser::{bytes_to_text, Scalar};
+
+/// What a scalar holds once Perl has numified it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Number {
+    Int(i64),
+    UInt(u64),
+    Float(f64),
+}
+
+/// Numify a string the way Perl does when it has nothing to warn about: surrounding whitespace,
+/// a sign, decimal digits with an optional fraction and exponent, `Inf` and `NaN`. Anything
+/// Perl would complain isn't numeric, such as `'42abc'` or `'0x1f'`, is an error rather than
+/// quietly becoming a different number.
+fn numify(s: &str) -> Result<Number> {
+    let trimmed = s.trim_matches(|c: char| c.is_ascii_whitespace());
+    // Perl exempts this string from the warning so that it can be a true zero.
+    if trimmed == "0 but true" {
+        return Ok(Number::Int(0));
+    }
+    if let Ok(i) = trimmed.parse() {
+        return Ok(Number::Int(i));
+    }
+    if let Ok(u) = trimmed.parse() {
+        return Ok(Number::UInt(u));
+    }
+    // Rust's float syntax is Perl's, down to spelling out `Inf`, `Infinity` and `NaN` in any case.
+    match trimmed.parse() {
+        Ok(f) => Ok(Number::Float(f)),
+        Err(_) => Err(<Error as de::Error>::invalid_value(
+            de::Unexpected::Str(s),
+            &"a number",
+        )),
+    }
+}
+
+/// Numify a scalar that is not a reference, or return `None` if it is `undef`.
+fn number(scalar: &Scalar) -> Option<Result<Number>> {
+    match scalar {
+        Scalar::Int(i) => Some(Ok(Number::Int(*i))),
+        Scalar::Float(f) => Some(Ok(Number::Float(*f))),
+        Scalar::String(s) => Some(numify(s)),
+        Scalar::Bytes(b) => Some(numify(&bytes_to_text(b))),
+        Scalar::Bool(b) => Some(Ok(Number::Int(*b as i64))),
+        Scalar::Undefined | Scalar::Reference(_) | Scalar::Object(_) => None,
+    }
+}
+
+/// Visit a scalar that is not a reference as a number, preferring an integer wherever the value
+/// is whole.
+pub(super) fn visit_integer<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    const TWO_POW_63: f64 = 9_223_372_036_854_775_808.0;
+    match number(&scalar) {
+        None => visit_literal(scalar, visitor),
+        Some(number) => match number? {
+            Number::Int(i) => visitor.visit_i64(i),
+            Number::UInt(u) => visitor.visit_u64(u),
+            Number::Float(f) if f.fract() == 0.0 && (-TWO_POW_63..TWO_POW_63).contains(&f) => {
+                visitor.visit_i64(f as i64)
+            }
+            Number::Float(f) if f.fract() == 0.0 && (0.0..2.0 * TWO_POW_63).contains(&f) => {
+                visitor.visit_u64(f as u64)
+            }
+            Number::Float(f) => visitor.visit_f64(f),
+        },
+    }
+}
+
+/// Visit a scalar that is not a reference as a number, like [`visit_integer`], except that a
+/// string holding an integer too wide for Perl is read exactly.
+pub(super) fn visit_wide_integer<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match wide_integer(&scalar) {
+        Some(Ok(i)) => visitor.visit_i128(i),
+        Some(Err(u)) => visitor.visit_u128(u),
+        None => visit_integer(scalar, visitor),
+    }
+}
+
+/// Visit a scalar that is not a reference as a number, leaving floats as they are.
+pub(super) fn visit_float<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match number(&scalar) {
+        None => visit_literal(scalar, visitor),
+        Some(number) => match number? {
+            Number::Int(i) => visitor.visit_i64(i),
+            Number::UInt(u) => visitor.visit_u64(u),
+            Number::Float(f) => visitor.visit_f64(f),
+        },
+    }
+}
+
+/// Visit a scalar that is not a reference as a string, writing numbers out as Perl prints them.
+pub(super) fn visit_string<'de, V>(scalar: Scalar<'de>, visitor: V) -> Result<V::Value>
+where
+    V: Visitor<'de>,
+{
+    match scalar {
+        Scalar::Int(i) => visitor.visit_str(itoa::Buffer::new().format(i)),
+        Scalar::Float(f) => visitor.visit_string(stringify_float(f)),
+        // The JSON modules overload stringification to give what was blessed.
+        Scalar::Bool(b) => visitor.visit_str(if b { "1" } else { "0" }),
+        scalar => visit_text(scalar, visitor),
+    }
+}
+
+/// Write a float the way Perl stringifies it, which is C's `%.15g`.
+fn stringify_float(f: f64) -> String {
+    const PRECISION: i32 = 15;
+    if f.is_nan() {
+        return "NaN".to_owned();
+    }
+    if f.is_infinite() {
+        return if f > 0.0 { "Inf" } else { "-Inf" }.to_owned();
+    }
+    // Round to the precision first, as that may carry into the exponent.
+    let scientific = format!("{:.*e}", PRECISION as usize - 1, f);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i32 = exponent.parse().unwrap_or(0);
+    if (-4..PRECISION).contains(&exponent) {
+        let fixed = format!("{:.*}", (PRECISION - 1 - exponent) as usize, f);
+        trim_fraction(&fixed).to_owned()
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!(
+            "{}e{}{:02}",
+            trim_fraction(mantissa),
+            sign,
+            exponent.unsigned_abs()
+        )
+    }
+}
+
+/// Drop trailing zeros after the decimal point, and the point too if nothing is left after it.
+fn trim_fraction(number: &str) -> &str {
+    if number.contains('.') {
+        number.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        number
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numify() {
+        assert_eq!(numify("42").unwrap(), Number::Int(42));
+        assert_eq!(numify(" -7\n").unwrap(), Number::Int(-7));
+        assert_eq!(numify("+3").unwrap(), Number::Int(3));
+        assert_eq!(numify("0 but true").unwrap(), Number::Int(0));
+        assert_eq!(
+            numify("18446744073709551615").unwrap(),
+            Number::UInt(u64::MAX)
+        );
+        assert_eq!(numify("1.5").unwrap(), Number::Float(1.5));
+        assert_eq!(numify(".5").unwrap(), Number::Float(0.5));
+        assert_eq!(numify("1e3").unwrap(), Number::Float(1000.0));
+        assert_eq!(numify("-Inf").unwrap(), Number::Float(f64::NEG_INFINITY));
+        assert!(matches!(numify("NaN").unwrap(), Number::Float(f) if f.is_nan()));
+        for s in ["", " ", "abc", "42abc", "0x1f", "1_000", "1e", "."] {
+            assert!(numify(s).is_err(), "{s:?}");
+        }
+    }
+
+    #[test]
+    fn test_stringify_float() {
+        assert_eq!(stringify_float(1.5), "1.5");
+        assert_eq!(stringify_float(3.0), "3");
+        assert_eq!(stringify_float(0.1 + 0.2), "0.3");
+        assert_eq!(stringify_float(1234.5), "1234.5");
+        assert_eq!(stringify_float(-0.25), "-0.25");
+        assert_eq!(stringify_float(0.0001), "0.0001");
+        assert_eq!(stringify_float(0.00001), "1e-05");
+        assert_eq!(stringify_float(1e15), "1e+15");
+        assert_eq!(stringify_float(123456789012345.0), "123456789012345");
+        assert_eq!(stringify_float(1e20), "1e+20");
+        assert_eq!(stringify_float(1.0 / 3.0), "0.333333333333333");
+        assert_eq!(stringify_float(f64::INFINITY), "Inf");
+        assert_eq!(stringify_float(f64::NAN), "NaN");
+    }
+}
@@ -0,0 +1,54 @@
+use std::borrow::Cow;
+
+use crate::error::Result;
+
+/// How to turn the bytes of a dump into text. Perl writes whatever bytes its strings hold, so
+/// dumps made without `use utf8` are often Latin-1, or a mixture.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    /// UTF-8, failing on any invalid sequence.
+    #[default]
+    Utf8,
+    /// UTF-8, replacing invalid sequences with U+FFFD.
+    Utf8Lossy,
+    /// ISO-8859-1, where every byte is the character with that code point.
+    Latin1,
+}
+
+impl Encoding {
+    /// Decode `input`, borrowing it where it is already valid text.
+    pub fn decode(self, input: &[u8]) -> Result<Cow<'_, str>> {
+        match self {
+            Encoding::Utf8 => Ok(Cow::Borrowed(std::str::from_utf8(input)?)),
+            Encoding::Utf8Lossy => Ok(String::from_utf8_lossy(input)),
+            Encoding::Latin1 => match std::str::from_utf8(input) {
+                Ok(text) if input.is_ascii() => Ok(Cow::Borrowed(text)),
+                _ => Ok(Cow::Owned(input.iter().map(|&b| char::from(b)).collect())),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Error;
+
+    #[test]
+    fn test_decode() {
+        let input = b"caf\xc3\xa9";
+        assert_eq!(Encoding::Utf8.decode(input).unwrap(), "café");
+        assert_eq!(Encoding::Utf8Lossy.decode(input).unwrap(), "café");
+        assert_eq!(Encoding::Latin1.decode(input).unwrap(), "cafÃ©");
+
+        let input = b"caf\xe9";
+        assert!(matches!(Encoding::Utf8.decode(input), Err(Error::Utf8(_))));
+        assert_eq!(Encoding::Utf8Lossy.decode(input).unwrap(), "caf\u{fffd}");
+        assert_eq!(Encoding::Latin1.decode(input).unwrap(), "café");
+
+        assert!(matches!(
+            Encoding::Latin1.decode(b"plain").unwrap(),
+            Cow::Borrowed("plain")
+        ));
+    }
+}
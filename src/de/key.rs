@@ -0,0 +1,169 @@
+use serde::{
+    de::{self, value::BorrowedStrDeserializer, IntoDeserializer, Visitor},
+    forward_to_deserialize_any,
+};
+use std::borrow::Cow;
+
+use crate::error::{Error, Result};
+
+/// Deserializes a hash key. Perl's keys are always strings, so numbers and booleans are parsed
+/// out of them when asked for, the way they were written.
+pub(super) struct KeyDeserializer<'de> {
+    key: Cow<'de, str>,
+}
+
+impl<'de> KeyDeserializer<'de> {
+    pub(super) fn new(key: Cow<'de, str>) -> Self {
+        KeyDeserializer { key }
+    }
+}
+
+/// Implement `deserialize_*` methods that parse the key as the type asked for.
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value>
+            where
+                V: Visitor<'de>,
+            {
+                match self.key.parse() {
+                    Ok(value) => visitor.$visit(value),
+                    Err(_) => Err(de::Error::invalid_value(
+                        de::Unexpected::Str(&self.key),
+                        &visitor,
+                    )),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.key {
+            Cow::Borrowed(key) => visitor.visit_borrowed_str(key),
+            Cow::Owned(key) => visitor.visit_string(key),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    /// The serializer writes `1` and `0`, and Perl stringifies false as `''`.
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match &*self.key {
+            "1" | "true" => visitor.visit_bool(true),
+            "0" | "" | "false" => visitor.visit_bool(false),
+            key => Err(de::Error::invalid_value(de::Unexpected::Str(key), &visitor)),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.key {
+            Cow::Borrowed(key) => visitor.visit_enum(BorrowedStrDeserializer::new(key)),
+            Cow::Owned(key) => visitor.visit_enum(key.into_deserializer()),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+    use std::collections::{BTreeMap, HashMap};
+
+    use crate::{from_perl, from_str, parser};
+
+    #[test]
+    fn test_numeric_keys() {
+        let input = "{ 1 => 'a', '2' => 'b', -3 => 'c' }";
+        let expected = HashMap::from([(1, "a"), (2, "b"), (-3, "c")]);
+        assert_eq!(from_str::<HashMap<i32, &str>>(input).unwrap(), expected);
+        let scalar = parser::parse(input).unwrap();
+        assert_eq!(from_perl::<HashMap<i32, &str>>(&scalar).unwrap(), expected);
+
+        let floats: BTreeMap<String, f64> = from_str("{ 1.5 => 1.5 }").unwrap();
+        assert_eq!(floats["1.5"], 1.5);
+        assert!(from_str::<HashMap<u32, i32>>("{ -1 => 1 }").is_err());
+        assert!(from_str::<HashMap<u32, i32>>("{ one => 1 }").is_err());
+    }
+
+    #[test]
+    fn test_typed_keys() {
+        #[derive(Deserialize, Debug, PartialEq, Eq, Hash)]
+        enum Status {
+            Open,
+            Closed,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq, Eq, Hash)]
+        struct Id(u64);
+
+        let counts: HashMap<Status, u32> = from_str("{ Open => 2, Closed => 3 }").unwrap();
+        assert_eq!(
+            counts,
+            HashMap::from([(Status::Open, 2), (Status::Closed, 3)])
+        );
+        assert!(from_str::<HashMap<Status, u32>>("{ Pending => 1 }").is_err());
+
+        let flags: HashMap<bool, &str> = from_str("{ 1 => 'yes', '' => 'no' }").unwrap();
+        assert_eq!(flags, HashMap::from([(true, "yes"), (false, "no")]));
+
+        let scalar = parser::parse("{ 42 => 'x' }").unwrap();
+        let ids: HashMap<Id, String> = from_perl(&scalar).unwrap();
+        assert_eq!(ids, HashMap::from([(Id(42), "x".to_owned())]));
+
+        let chars: HashMap<char, i32> = from_str("{ a => 1 }").unwrap();
+        assert_eq!(chars, HashMap::from([('a', 1)]));
+    }
+}
@@ -0,0 +1,908 @@
+use serde::{
+    de::{self, DeserializeSeed, IntoDeserializer, Visitor},
+    forward_to_deserialize_any, Deserialize,
+};
+use std::borrow::Cow;
+
+use super::{
+    coerce, deserialize_coerced, deserialize_key, single_key_expected, unexpected, visit_bytes,
+    visit_literal, visit_strict_bool, visit_text, visit_truthiness, visit_unit_variant,
+    visit_wide_integer, Options, RECURSION_LIMIT,
+};
+use crate::error::{Error, Result};
+use crate::parser::{Reference, Scalar};
+use crate::{bignum, blessed, perl_ref, registry, value};
+
+/// Deserializes from an already parsed [`Scalar`], borrowed or owned.
+pub struct ScalarDeserializer<'de> {
+    scalar: Cow<'de, Scalar<'de>>,
+    remaining_depth: usize,
+    options: Options,
+}
+
+impl<'de> ScalarDeserializer<'de> {
+    pub(crate) fn new(scalar: Cow<'de, Scalar<'de>>) -> Self {
+        ScalarDeserializer::with_options(scalar, Options::default())
+    }
+
+    pub(crate) fn with_options(scalar: Cow<'de, Scalar<'de>>, options: Options) -> Self {
+        ScalarDeserializer {
+            scalar,
+            remaining_depth: RECURSION_LIMIT,
+            options,
+        }
+    }
+
+    pub(super) fn nested(
+        scalar: Cow<'de, Scalar<'de>>,
+        remaining_depth: usize,
+        options: Options,
+    ) -> Self {
+        ScalarDeserializer {
+            scalar,
+            remaining_depth,
+            options,
+        }
+    }
+
+    /// Apply Perl's rules for turning numbers into strings and back whenever a number, string or
+    /// character is asked for, instead of insisting on the right kind of scalar.
+    pub fn coerce(mut self, coerce: bool) -> Self {
+        self.options.coerce = coerce;
+        self
+    }
+
+    /// Only accept `0` and `1` as booleans, rather than anything Perl considers true or false.
+    pub fn strict_booleans(mut self, strict: bool) -> Self {
+        self.options.strict_booleans = strict;
+        self
+    }
+
+    /// Give each blessed hash an extra entry holding its class under `tag`, as
+    /// [`Deserializer::class_tag`](super::Deserializer::class_tag) does.
+    pub fn class_tag(mut self, tag: &'static str) -> Self {
+        self.options.class_tag = Some(tag);
+        self
+    }
+
+    /// How many decimal digits each element of a Math::BigInt digit array holds, as
+    /// [`Deserializer::big_number_base_len`](super::Deserializer::big_number_base_len) says.
+    pub fn big_number_base_len(mut self, len: usize) -> Self {
+        self.options.big_number_base_len = len;
+        self
+    }
+
+    /// The number held by the scalar, if it is a Math::BigInt or Math::BigFloat.
+    fn big_number(&self) -> Option<Result<String>> {
+        match &*self.scalar {
+            Scalar::Object(object) if bignum::is_big_number_class(&object.class) => {
+                Some(bignum::decimal(
+                    &object.class,
+                    &object.value,
+                    self.options.big_number_base_len,
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the scalar is a Math::BigInt or Math::BigFloat, or a reference to one.
+    fn is_big_number(&self) -> bool {
+        let mut scalar = &*self.scalar;
+        while let Scalar::Reference(reference) = scalar {
+            match &**reference {
+                Reference::Scalar(referent) => scalar = referent,
+                _ => return false,
+            }
+        }
+        matches!(scalar, Scalar::Object(object) if bignum::is_big_number_class(&object.class))
+    }
+
+    /// Deserialize a 128-bit integer the way
+    /// [`Deserializer`](super::Deserializer) does: big numbers are read from the digits they
+    /// hold, but without coercion no other string is.
+    fn deserialize_wide_integer<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.options.coerce {
+            self.deserialize_literal(visitor, coerce::visit_wide_integer)
+        } else if self.is_big_number() {
+            self.deserialize_literal(visitor, visit_wide_integer)
+        } else {
+            self.deserialize_literal(visitor, visit_literal)
+        }
+    }
+
+    /// The depth left for the children of a reference, failing if there is none.
+    fn child_depth(&self) -> Result<usize> {
+        self.remaining_depth
+            .checked_sub(1)
+            .ok_or(Error::RecursionLimitExceeded)
+    }
+
+    /// Deserialize the scalar, visiting hashes and arrays and looking through scalar references,
+    /// but handing any other scalar to `literal` to visit.
+    fn deserialize_literal<V, F>(self, visitor: V, literal: F) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+        F: FnOnce(Scalar<'de>, V) -> Result<V::Value>,
+    {
+        if let Some(number) = self.big_number() {
+            return literal(Scalar::String(Cow::Owned(number?)), visitor);
+        }
+        let remaining_depth = self.child_depth();
+        match self.scalar {
+            // A borrowed tree is walked by reference, and lends out its strings whether or not
+            // the parser could borrow them from the input.
+            Cow::Borrowed(Scalar::Reference(reference)) => {
+                let remaining_depth = remaining_depth?;
+                match &**reference {
+                    Reference::Hash(h) => visitor.visit_map(MapAccess {
+                        iter: h
+                            .0
+                            .iter()
+                            .map(|(k, v)| (Cow::Borrowed(&**k), Cow::Borrowed(v))),
+                        value: None,
+                        remaining_depth,
+                        options: self.options,
+                    }),
+                    Reference::Array(a) => visitor.visit_seq(SeqAccess {
+                        iter: a.0.iter().map(Cow::Borrowed),
+                        remaining_depth,
+                        options: self.options,
+                    }),
+                    Reference::Scalar(s) => {
+                        ScalarDeserializer::nested(Cow::Borrowed(s), remaining_depth, self.options)
+                            .deserialize_literal(visitor, literal)
+                    }
+                }
+            }
+            Cow::Owned(Scalar::Reference(mut reference)) => {
+                let remaining_depth = remaining_depth?;
+                match &mut *reference {
+                    Reference::Hash(h) => visitor.visit_map(MapAccess {
+                        iter: std::mem::take(&mut h.0)
+                            .into_iter()
+                            .map(|(k, v)| (Cow::from(k), Cow::Owned(v))),
+                        value: None,
+                        remaining_depth,
+                        options: self.options,
+                    }),
+                    Reference::Array(a) => visitor.visit_seq(SeqAccess {
+                        iter: std::mem::take(&mut a.0).into_iter().map(Cow::Owned),
+                        remaining_depth,
+                        options: self.options,
+                    }),
+                    Reference::Scalar(s) => {
+                        let scalar = std::mem::replace(s, Scalar::Undefined);
+                        ScalarDeserializer::nested(
+                            Cow::Owned(scalar),
+                            remaining_depth,
+                            self.options,
+                        )
+                        .deserialize_literal(visitor, literal)
+                    }
+                }
+            }
+            // Objects are read as the reference that was blessed.
+            Cow::Borrowed(Scalar::Object(object)) => ScalarDeserializer::nested(
+                Cow::Borrowed(&object.value),
+                remaining_depth?,
+                self.options,
+            )
+            .deserialize_literal(visitor, literal),
+            Cow::Owned(Scalar::Object(mut object)) => {
+                let scalar = std::mem::replace(&mut object.value, Scalar::Undefined);
+                ScalarDeserializer::nested(Cow::Owned(scalar), remaining_depth?, self.options)
+                    .deserialize_literal(visitor, literal)
+            }
+            Cow::Borrowed(scalar) => literal(borrow_literal(scalar), visitor),
+            Cow::Owned(scalar) => literal(scalar, visitor),
+        }
+    }
+}
+
+impl<'de> ScalarDeserializer<'de> {
+    /// Deserialize a scalar reference as a newtype holding what it refers to.
+    fn deserialize_reference<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let remaining_depth = self.child_depth();
+        let unexpected = match self.scalar {
+            Cow::Borrowed(Scalar::Reference(reference)) => match &**reference {
+                Reference::Scalar(s) => {
+                    return visitor.visit_newtype_struct(ScalarDeserializer::nested(
+                        Cow::Borrowed(s),
+                        remaining_depth?,
+                        self.options,
+                    ))
+                }
+                Reference::Hash(_) => de::Unexpected::Map,
+                Reference::Array(_) => de::Unexpected::Seq,
+            },
+            Cow::Owned(Scalar::Reference(mut reference)) => match &mut *reference {
+                Reference::Scalar(s) => {
+                    let scalar = std::mem::replace(s, Scalar::Undefined);
+                    return visitor.visit_newtype_struct(ScalarDeserializer::nested(
+                        Cow::Owned(scalar),
+                        remaining_depth?,
+                        self.options,
+                    ));
+                }
+                Reference::Hash(_) => de::Unexpected::Map,
+                Reference::Array(_) => de::Unexpected::Seq,
+            },
+            Cow::Borrowed(Scalar::Object(_)) | Cow::Owned(Scalar::Object(_)) => {
+                de::Unexpected::Other("an object")
+            }
+            scalar => return Err(de::Error::invalid_type(unexpected(&scalar), &visitor)),
+        };
+        Err(de::Error::invalid_type(unexpected, &visitor))
+    }
+
+    /// Hand the scalar over whole for a [`Scalar`](crate::Scalar) to be built from, with scalar
+    /// references and objects as enum variants named for [`PerlRef`](crate::PerlRef) and
+    /// [`Blessed`](crate::Blessed) rather than looked through.
+    pub(super) fn deserialize_tree<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let variant = match &*self.scalar {
+            Scalar::Reference(reference) if matches!(**reference, Reference::Scalar(_)) => {
+                perl_ref::NAME
+            }
+            Scalar::Object(_) => blessed::NAME,
+            _ => return self.deserialize_literal(visitor, visit_literal),
+        };
+        visitor.visit_enum(EnumAccess {
+            key: Cow::Borrowed(variant),
+            value: self,
+        })
+    }
+
+    /// Deserialize an object as a map from `value` to the reference that was blessed and `class`
+    /// to its class.
+    fn deserialize_blessed<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let remaining_depth = self.child_depth();
+        let (value, class) = match self.scalar {
+            Cow::Borrowed(Scalar::Object(object)) => {
+                (Cow::Borrowed(&object.value), Cow::Borrowed(&*object.class))
+            }
+            Cow::Owned(Scalar::Object(mut object)) => (
+                Cow::Owned(std::mem::replace(&mut object.value, Scalar::Undefined)),
+                std::mem::take(&mut object.class),
+            ),
+            _ => {
+                return Err(de::Error::invalid_type(
+                    de::Unexpected::Other("an unblessed value"),
+                    &visitor,
+                ))
+            }
+        };
+        let entries = [
+            (Cow::Borrowed(blessed::FIELDS[0]), value),
+            (
+                Cow::Borrowed(blessed::FIELDS[1]),
+                Cow::Owned(Scalar::String(class)),
+            ),
+        ];
+        visitor.visit_map(MapAccess {
+            iter: entries.into_iter(),
+            value: None,
+            remaining_depth: remaining_depth?,
+            options: self.options,
+        })
+    }
+
+    /// Deserialize the scalar like `deserialize_any`, except that a blessed hash gets an entry
+    /// holding its class under `tag`.
+    fn deserialize_tagged<V>(self, tag: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.big_number().is_some() {
+            return self.deserialize_literal(visitor, visit_literal);
+        }
+        let remaining_depth = self.child_depth();
+        let tag = Cow::Borrowed(tag);
+        match self.scalar {
+            Cow::Borrowed(Scalar::Object(object)) => match &object.value {
+                Scalar::Reference(reference) => match &**reference {
+                    Reference::Hash(h) => {
+                        let class = Scalar::String(Cow::Borrowed(&*object.class));
+                        visitor.visit_map(MapAccess {
+                            iter: h
+                                .0
+                                .iter()
+                                .map(|(k, v)| (Cow::Borrowed(&**k), Cow::Borrowed(v)))
+                                .chain(std::iter::once((tag, Cow::Owned(class)))),
+                            value: None,
+                            remaining_depth: remaining_depth?,
+                            options: self.options,
+                        })
+                    }
+                    _ => self.deserialize_literal(visitor, visit_literal),
+                },
+                _ => self.deserialize_literal(visitor, visit_literal),
+            },
+            Cow::Owned(Scalar::Object(mut object)) => match &mut object.value {
+                Scalar::Reference(reference) => match &mut **reference {
+                    Reference::Hash(h) => {
+                        let class = Scalar::String(std::mem::take(&mut object.class));
+                        visitor.visit_map(MapAccess {
+                            iter: std::mem::take(&mut h.0)
+                                .into_iter()
+                                .map(|(k, v)| (Cow::from(k), Cow::Owned(v)))
+                                .chain(std::iter::once((tag, Cow::Owned(class)))),
+                            value: None,
+                            remaining_depth: remaining_depth?,
+                            options: self.options,
+                        })
+                    }
+                    _ => ScalarDeserializer::nested(
+                        Cow::Owned(Scalar::Object(object)),
+                        self.remaining_depth,
+                        self.options,
+                    )
+                    .deserialize_literal(visitor, visit_literal),
+                },
+                _ => ScalarDeserializer::nested(
+                    Cow::Owned(Scalar::Object(object)),
+                    self.remaining_depth,
+                    self.options,
+                )
+                .deserialize_literal(visitor, visit_literal),
+            },
+            scalar => ScalarDeserializer::nested(scalar, self.remaining_depth, self.options)
+                .deserialize_literal(visitor, visit_literal),
+        }
+    }
+}
+
+/// A copy of a scalar that is not a reference, borrowing its string.
+fn borrow_literal<'de>(scalar: &'de Scalar<'de>) -> Scalar<'de> {
+    match scalar {
+        Scalar::Int(i) => Scalar::Int(*i),
+        Scalar::Float(f) => Scalar::Float(*f),
+        Scalar::String(s) => Scalar::String(Cow::Borrowed(s)),
+        Scalar::Bytes(b) => Scalar::Bytes(Cow::Borrowed(b)),
+        Scalar::Bool(b) => Scalar::Bool(*b),
+        // References are never literals.
+        Scalar::Undefined | Scalar::Reference(_) | Scalar::Object(_) => Scalar::Undefined,
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ScalarDeserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        match self.options.class_tag {
+            Some(tag) => self.deserialize_tagged(tag, visitor),
+            None => self.deserialize_literal(visitor, visit_literal),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, name: &'static str, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == perl_ref::NAME {
+            self.deserialize_reference(visitor)
+        } else if name == registry::NAME {
+            let options = self.options;
+            registry::hand_over(self, options, visitor)
+        } else if name == value::de::NAME {
+            self.deserialize_tree(visitor)
+        } else {
+            visitor.visit_newtype_struct(self)
+        }
+    }
+
+    fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_literal(visitor, visit_literal)
+    }
+
+    fn deserialize_struct<V>(
+        self,
+        name: &'static str,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if name == blessed::NAME {
+            self.deserialize_blessed(visitor)
+        } else {
+            self.deserialize_literal(visitor, visit_literal)
+        }
+    }
+
+    /// `undef` is `None` and anything else is `Some`. A reference to `undef` is `None` too, but
+    /// any other reference is left whole for `Some`, like the parsing deserializer does.
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let mut scalar = &*self.scalar;
+        while let Scalar::Reference(reference) = scalar {
+            match &**reference {
+                Reference::Scalar(referent) => scalar = referent,
+                _ => break,
+            }
+        }
+        match scalar {
+            Scalar::Undefined => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    /// Unit variants are strings, and the others hashes with the variant name as their only key,
+    /// the way the serializer writes them.
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        let remaining_depth = self.child_depth();
+        match self.scalar {
+            Cow::Borrowed(Scalar::Reference(reference)) => match &**reference {
+                Reference::Scalar(s) => {
+                    ScalarDeserializer::nested(Cow::Borrowed(s), remaining_depth?, self.options)
+                        .deserialize_enum(name, variants, visitor)
+                }
+                Reference::Hash(h) => {
+                    let mut entries = h.0.iter();
+                    match (entries.next(), entries.next()) {
+                        (Some((key, value)), None) => visitor.visit_enum(EnumAccess {
+                            key: Cow::Borrowed(&**key),
+                            value: ScalarDeserializer::nested(
+                                Cow::Borrowed(value),
+                                remaining_depth?,
+                                self.options,
+                            ),
+                        }),
+                        _ => Err(single_key_expected()),
+                    }
+                }
+                Reference::Array(_) => ScalarDeserializer { ..self }.deserialize_any(visitor),
+            },
+            Cow::Owned(Scalar::Reference(mut reference)) => match &mut *reference {
+                Reference::Scalar(s) => {
+                    let scalar = std::mem::replace(s, Scalar::Undefined);
+                    ScalarDeserializer::nested(Cow::Owned(scalar), remaining_depth?, self.options)
+                        .deserialize_enum(name, variants, visitor)
+                }
+                Reference::Hash(h) if h.0.len() == 1 => {
+                    let Some((key, value)) = std::mem::take(&mut h.0).into_iter().next() else {
+                        return Err(single_key_expected());
+                    };
+                    visitor.visit_enum(EnumAccess {
+                        key: key.into(),
+                        value: ScalarDeserializer::nested(
+                            Cow::Owned(value),
+                            remaining_depth?,
+                            self.options,
+                        ),
+                    })
+                }
+                Reference::Hash(_) => Err(single_key_expected()),
+                Reference::Array(_) => ScalarDeserializer::nested(
+                    Cow::Owned(Scalar::Reference(reference)),
+                    self.remaining_depth,
+                    self.options,
+                )
+                .deserialize_any(visitor),
+            },
+            scalar => ScalarDeserializer::nested(scalar, self.remaining_depth, self.options)
+                .deserialize_literal(visitor, visit_unit_variant),
+        }
+    }
+
+    /// Anything Perl considers true or false, looking through `\1` and `JSON::PP::Boolean`
+    /// objects like the parsing deserializer does.
+    fn deserialize_bool<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        if self.options.strict_booleans {
+            self.deserialize_literal(visitor, visit_strict_bool)
+        } else {
+            self.deserialize_literal(visitor, visit_truthiness)
+        }
+    }
+
+    deserialize_coerced! {
+        deserialize_i8 => coerce::visit_integer, visit_literal;
+        deserialize_i16 => coerce::visit_integer, visit_literal;
+        deserialize_i32 => coerce::visit_integer, visit_literal;
+        deserialize_i64 => coerce::visit_integer, visit_literal;
+        deserialize_u8 => coerce::visit_integer, visit_literal;
+        deserialize_u16 => coerce::visit_integer, visit_literal;
+        deserialize_u32 => coerce::visit_integer, visit_literal;
+        deserialize_u64 => coerce::visit_integer, visit_literal;
+        deserialize_f32 => coerce::visit_float, visit_literal;
+        deserialize_f64 => coerce::visit_float, visit_literal;
+        deserialize_char => coerce::visit_string, visit_literal;
+        deserialize_str => coerce::visit_string, visit_text;
+        deserialize_string => coerce::visit_string, visit_text;
+    }
+
+    fn deserialize_i128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_wide_integer(visitor)
+    }
+
+    fn deserialize_u128<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_wide_integer(visitor)
+    }
+
+    fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_literal(visitor, visit_bytes)
+    }
+
+    fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        self.deserialize_literal(visitor, visit_bytes)
+    }
+
+    forward_to_deserialize_any! {
+        unit unit_struct seq tuple tuple_struct identifier ignored_any
+    }
+
+    fn is_human_readable(&self) -> bool {
+        true
+    }
+}
+
+struct SeqAccess<I> {
+    iter: I,
+    remaining_depth: usize,
+    options: Options,
+}
+
+impl<'de, I> de::SeqAccess<'de> for SeqAccess<I>
+where
+    I: Iterator<Item = Cow<'de, Scalar<'de>>>,
+{
+    type Error = Error;
+
+    fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some(scalar) => seed
+                .deserialize(ScalarDeserializer::nested(
+                    scalar,
+                    self.remaining_depth,
+                    self.options,
+                ))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+struct MapAccess<'de, I> {
+    iter: I,
+    value: Option<Cow<'de, Scalar<'de>>>,
+    remaining_depth: usize,
+    options: Options,
+}
+
+impl<'de, I> de::MapAccess<'de> for MapAccess<'de, I>
+where
+    I: Iterator<Item = (Cow<'de, str>, Cow<'de, Scalar<'de>>)>,
+{
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+    where
+        K: DeserializeSeed<'de>,
+    {
+        match self.iter.next() {
+            Some((key, value)) => {
+                self.value = Some(value);
+                deserialize_key(seed, key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        let value = self
+            .value
+            .take()
+            .ok_or_else(|| <Error as de::Error>::custom("value is missing"))?;
+        seed.deserialize(ScalarDeserializer::nested(
+            value,
+            self.remaining_depth,
+            self.options,
+        ))
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.iter.size_hint() {
+            (lower, Some(upper)) if lower == upper => Some(upper),
+            _ => None,
+        }
+    }
+}
+
+/// Reads the variant of an enum written as a hash with a single key, or of the enum a tree is
+/// handed over as.
+struct EnumAccess<'de> {
+    key: Cow<'de, str>,
+    value: ScalarDeserializer<'de>,
+}
+
+impl<'de> de::EnumAccess<'de> for EnumAccess<'de> {
+    type Error = Error;
+    type Variant = ScalarDeserializer<'de>;
+
+    fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self::Variant)>
+    where
+        V: DeserializeSeed<'de>,
+    {
+        Ok((deserialize_key(seed, self.key)?, self.value))
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for ScalarDeserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+    where
+        T: DeserializeSeed<'de>,
+    {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_seq(self, visitor)
+    }
+
+    fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+    where
+        V: Visitor<'de>,
+    {
+        de::Deserializer::deserialize_map(self, visitor)
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for &'de Scalar<'de> {
+    type Deserializer = ScalarDeserializer<'de>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        ScalarDeserializer::new(Cow::Borrowed(self))
+    }
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Scalar<'de> {
+    type Deserializer = ScalarDeserializer<'de>;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        ScalarDeserializer::new(Cow::Owned(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_perl, parser};
+    use serde::Deserialize;
+
+    #[test]
+    fn test_deserialize() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            a: i32,
+            b: String,
+        }
+
+        let scalar = perl!({ a => 42, b => "hello" });
+
+        let test: Test = from_perl(&scalar).unwrap();
+        assert_eq!(
+            test,
+            Test {
+                a: 42,
+                b: "hello".to_string()
+            }
+        );
+    }
+
+    // let's parse some perl
+    #[test]
+    fn test_deserialize_perl() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Test {
+            a: i32,
+            b: String,
+            topic: String,
+            c: Vec<i32>,
+        }
+
+        let scalar =
+            parser::parse(r#"{a => 42, 'b' => 'hello', "topic" => "\nworld", 'c' => [1, 2, 3]}"#)
+                .unwrap();
+        let test: Test = from_perl(&scalar).unwrap();
+        assert_eq!(
+            test,
+            Test {
+                a: 42,
+                b: "hello".to_string(),
+                topic: "\nworld".to_string(),
+                c: vec![1, 2, 3]
+            }
+        );
+    }
+
+    #[test]
+    fn test_byte_strings() {
+        let scalar = parser::parse(r#"[ "\xff\xfe", 'text' ]"#).unwrap();
+        let (bytes, text): (&[u8], &[u8]) = from_perl(&scalar).unwrap();
+        assert_eq!(bytes, b"\xff\xfe");
+        assert_eq!(text, b"text");
+        let (bytes, _): (String, String) = from_perl(&scalar).unwrap();
+        assert_eq!(bytes, "ÿþ");
+    }
+
+    #[test]
+    fn test_option() {
+        let scalar = parser::parse("[ undef, 5, \\undef, \\\\6 ]").unwrap();
+        let values: Vec<Option<i32>> = from_perl(&scalar).unwrap();
+        assert_eq!(values, vec![None, Some(5), None, Some(6)]);
+
+        let scalar = parser::parse("[ undef, \\[1], [2] ]").unwrap();
+        let values: Vec<Option<Vec<i32>>> = from_perl(&scalar).unwrap();
+        assert_eq!(values, vec![None, Some(vec![1]), Some(vec![2])]);
+        let values: Vec<Option<Vec<i32>>> =
+            Deserialize::deserialize(scalar.into_deserializer()).unwrap();
+        assert_eq!(values, vec![None, Some(vec![1]), Some(vec![2])]);
+    }
+
+    #[test]
+    fn test_coerce() {
+        let scalar = parser::parse("[ '42', \\'-1.5', 3, 0.5, 7 ]").unwrap();
+        assert!(from_perl::<(i32, f64, f64, String, char)>(&scalar).is_err());
+        let de = ScalarDeserializer::new(Cow::Borrowed(&scalar)).coerce(true);
+        let values: (i32, f64, f64, String, char) = Deserialize::deserialize(de).unwrap();
+        assert_eq!(values, (42, -1.5, 3.0, "0.5".to_owned(), '7'));
+    }
+
+    #[test]
+    fn test_bool() {
+        let input = "[ 1, '', 'a', undef, \\0, bless( do{\\(my $o = 1)}, 'JSON::PP::Boolean' ) ]";
+        let scalar = parser::parse(input).unwrap();
+        let values: Vec<bool> = from_perl(&scalar).unwrap();
+        assert_eq!(values, [true, false, true, false, false, true]);
+
+        let de = ScalarDeserializer::new(Cow::Borrowed(&scalar)).strict_booleans(true);
+        assert!(Vec::<bool>::deserialize(de).is_err());
+        let scalar = parser::parse("[ 0, '1' ]").unwrap();
+        let de = ScalarDeserializer::new(Cow::Owned(scalar)).strict_booleans(true);
+        assert_eq!(Vec::<bool>::deserialize(de).unwrap(), [false, true]);
+    }
+
+    #[test]
+    fn test_enum() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        enum E {
+            Unit,
+            Newtype(u32),
+            Tuple(u32, u32),
+            Struct { a: u32 },
+        }
+
+        let scalar = parser::parse(
+            "[ 'Unit', {Unit=>undef}, {Newtype=>1}, \\{Tuple=>[1,2]}, {Struct=>{a=>1}} ]",
+        )
+        .unwrap();
+        let expected = vec![
+            E::Unit,
+            E::Unit,
+            E::Newtype(1),
+            E::Tuple(1, 2),
+            E::Struct { a: 1 },
+        ];
+        assert_eq!(from_perl::<Vec<E>>(&scalar).unwrap(), expected);
+        let owned: Vec<E> = Deserialize::deserialize(scalar.into_deserializer()).unwrap();
+        assert_eq!(owned, expected);
+
+        for input in [
+            "{ Unit => undef, Newtype => 1 }",
+            "{}",
+            "[ 'Unit' ]",
+            "'Other'",
+        ] {
+            let scalar = parser::parse(input).unwrap();
+            assert!(from_perl::<E>(&scalar).is_err(), "{input}");
+            assert!(
+                E::deserialize(scalar.into_deserializer()).is_err(),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_recursion_limit() {
+        let input = format!("{}1", "\\\\".repeat(100_000));
+        let scalar = parser::parse(&input).unwrap();
+        let err = from_perl::<serde::de::IgnoredAny>(&scalar).unwrap_err();
+        assert!(matches!(err, Error::RecursionLimitExceeded));
+        let err = serde::de::IgnoredAny::deserialize(scalar.into_deserializer()).unwrap_err();
+        assert!(matches!(err, Error::RecursionLimitExceeded));
+    }
+
+    #[test]
+    fn test_deserialize_borrowed() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Summary<'a> {
+            name: &'a str,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Detail<'a> {
+            name: &'a str,
+            #[serde(borrow)]
+            tags: Vec<&'a str>,
+        }
+
+        // Escaped strings are owned by the tree, but can still be borrowed from it.
+        let scalar = parser::parse(r#"{ name => "a\tb", tags => [ 'x', "y\n" ] }"#).unwrap();
+        let summary: Summary = from_perl(&scalar).unwrap();
+        assert_eq!(summary, Summary { name: "a\tb" });
+        let detail: Detail = from_perl(&scalar).unwrap();
+        assert_eq!(
+            detail,
+            Detail {
+                name: "a\tb",
+                tags: vec!["x", "y\n"],
+            }
+        );
+    }
+}
@@ -0,0 +1,308 @@
+use serde::de::{Deserialize, DeserializeOwned};
+use std::{io, marker::PhantomData};
+
+use super::Deserializer;
+use crate::error::{Error, Result};
+
+/// Iterates over any number of values written one after another, like a log file that has one
+/// `Dumper` output appended per event. Each value may be bare or a `$VAR1 = ...;` statement.
+///
+/// Iteration stops after the first error.
+pub struct StreamDeserializer<R, T> {
+    read: R,
+    offset: usize,
+    failed: bool,
+    output: PhantomData<T>,
+}
+
+/// The input of a [`StreamDeserializer`] reading from a string.
+pub struct StrRead<'de> {
+    input: &'de str,
+}
+
+/// The input of a [`StreamDeserializer`] reading from an [`io::Read`]. Only the value being
+/// deserialized is buffered, so errors give positions from the start of that value.
+pub struct IoRead<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    /// Where the unread part of `buffer` starts.
+    start: usize,
+    eof: bool,
+}
+
+impl<'de, T> StreamDeserializer<StrRead<'de>, T>
+where
+    T: Deserialize<'de>,
+{
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(input: &'de str) -> Self {
+        StreamDeserializer::new(StrRead { input })
+    }
+}
+
+impl<R, T> StreamDeserializer<IoRead<R>, T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    pub fn from_reader(reader: R) -> Self {
+        StreamDeserializer::new(IoRead {
+            reader,
+            buffer: Vec::new(),
+            start: 0,
+            eof: false,
+        })
+    }
+}
+
+impl<R, T> StreamDeserializer<R, T> {
+    fn new(read: R) -> Self {
+        StreamDeserializer {
+            read,
+            offset: 0,
+            failed: false,
+            output: PhantomData,
+        }
+    }
+
+    /// How many bytes of input the values returned so far, and anything before them, took up.
+    pub fn byte_offset(&self) -> usize {
+        self.offset
+    }
+
+    fn fail(&mut self, err: Error) -> Option<Result<T>> {
+        self.failed = true;
+        Some(Err(err))
+    }
+}
+
+impl<'de, T> Iterator for StreamDeserializer<StrRead<'de>, T>
+where
+    T: Deserialize<'de>,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed {
+            return None;
+        }
+        let mut de = Deserializer::starting_at(self.read.input, self.offset);
+        match de.next_statement() {
+            Ok(value) => {
+                self.offset = de.parser.offset();
+                value.map(Ok)
+            }
+            Err(err) => self.fail(err),
+        }
+    }
+}
+
+impl<R, T> Iterator for StreamDeserializer<IoRead<R>, T>
+where
+    R: io::Read,
+    T: DeserializeOwned,
+{
+    type Item = Result<T>;
+
+    fn next(&mut self) -> Option<Result<T>> {
+        if self.failed {
+            return None;
+        }
+        loop {
+            let (text, complete) = match self.read.text() {
+                Ok(text) => text,
+                Err(err) => return self.fail(err),
+            };
+            let mut de = Deserializer::from_str(text);
+            let result = de.next_statement();
+
+            // Until the reader runs dry, a value that reaches the end of the buffer might carry
+            // on past it, and an error there might only be a value cut short.
+            let cut_short = matches!(result, Err(Error::Eof { .. }));
+            if !complete && (cut_short || de.parser.exhausted()) {
+                if let Err(err) = self.read.fill() {
+                    return self.fail(err.into());
+                }
+                continue;
+            }
+
+            return match result {
+                Ok(value) => {
+                    let consumed = de.parser.offset();
+                    self.read.start += consumed;
+                    self.offset += consumed;
+                    value.map(Ok)
+                }
+                Err(err) => self.fail(err),
+            };
+        }
+    }
+}
+
+impl<R> IoRead<R>
+where
+    R: io::Read,
+{
+    /// The unread part of the buffer that is valid UTF-8, and whether it runs to the end of the
+    /// input.
+    fn text(&self) -> Result<(&str, bool)> {
+        let unread = &self.buffer[self.start..];
+        match std::str::from_utf8(unread) {
+            Ok(text) => Ok((text, self.eof)),
+            // A character split between two reads.
+            Err(err) if err.error_len().is_none() && !self.eof => {
+                let text = std::str::from_utf8(&unread[..err.valid_up_to()])?;
+                Ok((text, false))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Drop what has been read from the buffer and read at least as much again as is left, so
+    /// that a long value is parsed a logarithmic number of times.
+    fn fill(&mut self) -> io::Result<()> {
+        self.buffer.drain(..self.start);
+        self.start = 0;
+
+        let len = self.buffer.len();
+        self.buffer.resize(len + len.max(8 * 1024), 0);
+        loop {
+            match self.reader.read(&mut self.buffer[len..]) {
+                Ok(n) => {
+                    self.buffer.truncate(len + n);
+                    self.eof = n == 0;
+                    return Ok(());
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => {
+                    self.buffer.truncate(len);
+                    return Err(err);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+    use std::collections::HashMap;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Event {
+        id: u32,
+        tags: Vec<String>,
+    }
+
+    const EVENTS: &str = "
+        $VAR1 = { id => 1, tags => [ 'a' ] };
+        # a comment between events
+        $VAR1 = {
+          'id' => 2,
+          'tags' => [ \"b\\x{e9}\", 'c' ]
+        };
+        { id => 3, tags => [] }
+    ";
+
+    fn expected() -> Vec<Event> {
+        vec![
+            Event {
+                id: 1,
+                tags: vec!["a".to_string()],
+            },
+            Event {
+                id: 2,
+                tags: vec!["bé".to_string(), "c".to_string()],
+            },
+            Event {
+                id: 3,
+                tags: vec![],
+            },
+        ]
+    }
+
+    /// Hands out its input a byte at a time, to split every token and character across reads.
+    struct Trickle<'a>(&'a [u8]);
+
+    impl io::Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let Some((&first, rest)) = self.0.split_first() else {
+                return Ok(0);
+            };
+            buf[0] = first;
+            self.0 = rest;
+            Ok(1)
+        }
+    }
+
+    #[test]
+    fn test_stream_str() {
+        let mut stream = StreamDeserializer::<_, Event>::from_str(EVENTS);
+        let mut events = Vec::new();
+        let mut offsets = Vec::new();
+        while let Some(event) = stream.next() {
+            events.push(event.unwrap());
+            offsets.push(stream.byte_offset());
+        }
+        assert_eq!(events, expected());
+        assert!(EVENTS[..offsets[0]].ends_with("};"));
+        assert!(EVENTS[..offsets[1]].ends_with("};"));
+        assert!(EVENTS[..offsets[2]].ends_with("[] }"));
+    }
+
+    #[test]
+    fn test_stream_reader() {
+        let events: Vec<Event> = StreamDeserializer::from_reader(Trickle(EVENTS.as_bytes()))
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(events, expected());
+
+        let stream = StreamDeserializer::<_, Event>::from_reader(EVENTS.as_bytes());
+        assert_eq!(stream.map(Result::unwrap).collect::<Vec<_>>(), expected());
+    }
+
+    #[test]
+    fn test_stream_bare_values() {
+        let input = "12 34 [5] 'six' {seven=>7}";
+        let values: Vec<serde::de::IgnoredAny> = StreamDeserializer::from_str(input)
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(values.len(), 5);
+
+        // Numbers are not cut short where a read ends.
+        let values: Vec<i64> = StreamDeserializer::from_reader(Trickle(b"12 34 1_000"))
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(values, vec![12, 34, 1000]);
+
+        let empty: Vec<i64> = StreamDeserializer::from_reader(Trickle(b"  # nothing\n"))
+            .collect::<Result<_>>()
+            .unwrap();
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        let input = "1 2 ] 4";
+        let mut stream = StreamDeserializer::<_, i64>::from_str(input);
+        assert_eq!(stream.next().unwrap().unwrap(), 1);
+        assert_eq!(stream.next().unwrap().unwrap(), 2);
+        assert!(matches!(
+            stream.next(),
+            Some(Err(Error::Syntax { column: 5, .. }))
+        ));
+        assert!(stream.next().is_none());
+        assert_eq!(stream.byte_offset(), 3);
+
+        let mut stream = StreamDeserializer::<_, HashMap<String, i64>>::from_reader(Trickle(
+            b"{ a => 1 } { b => ",
+        ));
+        assert!(stream.next().unwrap().is_ok());
+        assert!(matches!(stream.next(), Some(Err(Error::Eof { .. }))));
+        assert!(stream.next().is_none());
+
+        let mut stream = StreamDeserializer::<_, String>::from_reader(&b"'\xff'"[..]);
+        assert!(matches!(stream.next(), Some(Err(Error::Utf8(_)))));
+    }
+}
@@ -10,8 +10,24 @@ pub enum Error {
     #[error("{0}")]
     Message(String),
 
-    #[error("parse error: {0}")]
-    Nom(String),
+    #[error("{message} at line {line} column {column}")]
+    Syntax {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+
+    #[error("unexpected end of input at line {line} column {column}")]
+    Eof { line: usize, column: usize },
+
+    #[error("recursion limit exceeded")]
+    RecursionLimitExceeded,
+
+    #[error("{0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("{0}")]
+    Utf8(#[from] std::str::Utf8Error),
     // Zero or more variants that can be created directly by the Serializer and
     // Deserializer without going through `ser::Error` and `de::Error`. These
     // are specific to the format, in this case JSON.
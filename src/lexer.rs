@@ -0,0 +1,640 @@
+use memchr::{memchr2, memchr3};
+use std::{borrow::Cow, fmt};
+
+use crate::error::{Error, Result};
+
+/// These are all the characters that can be used as delimiters in Perl's `q` operator, I think.
+/// There might be more, and possibly unicode characters, but I don't need those for now.
+const PUNCTUATION: &str = r##"!"#$%&'(*+,-/:;<=?@[\^`{|~"##;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token<'a> {
+    Int(i64),
+    Float(f64),
+    /// A quoted string, borrowed from the input unless it contained escapes.
+    String(Cow<'a, str>),
+    /// A double-quoted string of bytes, written with escapes like `\xff` or `\377`.
+    Bytes(Vec<u8>),
+    /// An unquoted word such as `undef` or a hash key on the left of `=>`.
+    Bareword(&'a str),
+    LeftBracket,
+    RightBracket,
+    LeftBrace,
+    RightBrace,
+    LeftParen,
+    RightParen,
+    Backslash,
+    Comma,
+    FatComma,
+    /// A scalar variable such as `$VAR1`, which Data::Dumper assigns each value to.
+    Variable(&'a str),
+    Equals,
+    Semicolon,
+    Eof,
+}
+
+impl Token<'_> {
+    /// Whether this token is a whole scalar on its own, rather than the start of a reference.
+    pub fn is_literal(&self) -> bool {
+        matches!(
+            self,
+            Token::Int(_)
+                | Token::Float(_)
+                | Token::String(_)
+                | Token::Bytes(_)
+                | Token::Bareword("undef")
+        )
+    }
+}
+
+impl fmt::Display for Token<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Int(_) | Token::Float(_) => f.write_str("number"),
+            Token::String(_) | Token::Bytes(_) => f.write_str("string"),
+            Token::Bareword(word) => write!(f, "bareword `{word}`"),
+            Token::LeftBracket => f.write_str("`[`"),
+            Token::RightBracket => f.write_str("`]`"),
+            Token::LeftBrace => f.write_str("`{`"),
+            Token::RightBrace => f.write_str("`}`"),
+            Token::LeftParen => f.write_str("`(`"),
+            Token::RightParen => f.write_str("`)`"),
+            Token::Backslash => f.write_str("`\\`"),
+            Token::Comma => f.write_str("`,`"),
+            Token::FatComma => f.write_str("`=>`"),
+            Token::Variable(name) => write!(f, "variable `{name}`"),
+            Token::Equals => f.write_str("`=`"),
+            Token::Semicolon => f.write_str("`;`"),
+            Token::Eof => f.write_str("end of input"),
+        }
+    }
+}
+
+/// Splits Data::Dumper output into tokens in a single pass over the input.
+#[derive(Clone)]
+pub struct Lexer<'a> {
+    input: &'a str,
+    pos: usize,
+    /// Where the most recently lexed token starts, for error messages.
+    start: usize,
+    /// Where the most recently returned token ends.
+    consumed: usize,
+    peeked: Option<Token<'a>>,
+}
+
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer::starting_at(input, 0)
+    }
+
+    /// A lexer that skips the first `offset` bytes of `input`, but still reports positions from
+    /// the start of it.
+    pub fn starting_at(input: &'a str, offset: usize) -> Self {
+        Lexer {
+            input,
+            pos: offset,
+            start: offset,
+            consumed: offset,
+            peeked: None,
+        }
+    }
+
+    pub fn peek(&mut self) -> Result<&Token<'a>> {
+        let token = match self.peeked.take() {
+            Some(token) => token,
+            None => self.lex()?,
+        };
+        Ok(self.peeked.insert(token))
+    }
+
+    pub fn next_token(&mut self) -> Result<Token<'a>> {
+        let token = match self.peeked.take() {
+            Some(token) => token,
+            None => self.lex()?,
+        };
+        // A peeked token is always the last one lexed, so it ends where lexing stopped.
+        self.consumed = self.pos;
+        Ok(token)
+    }
+
+    /// The byte offset just past the last token returned by [`Lexer::next_token`].
+    pub fn offset(&self) -> usize {
+        self.consumed
+    }
+
+    /// Whether lexing has reached the end of the input, so that more input could have changed
+    /// the last token or error. Telling a token apart takes at most one byte past its start.
+    pub fn exhausted(&self) -> bool {
+        self.pos >= self.input.len() || self.start + 1 >= self.input.len()
+    }
+
+    /// An error for `token`, which was the last token returned, when `expected` was wanted.
+    pub fn unexpected(&self, token: &Token<'_>, expected: &str) -> Error {
+        match token {
+            Token::Eof => self.eof(),
+            token => self.error(format!("expected {expected}, found {token}")),
+        }
+    }
+
+    pub fn error(&self, message: String) -> Error {
+        let (line, column) = self.position(self.start);
+        Error::Syntax {
+            message,
+            line,
+            column,
+        }
+    }
+
+    fn eof(&self) -> Error {
+        let (line, column) = self.position(self.input.len());
+        Error::Eof { line, column }
+    }
+
+    /// The 1-based line and column of a byte offset into the input.
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let before = self.input.get(..offset).unwrap_or(self.input);
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let column = before[line_start..].chars().count() + 1;
+        (line, column)
+    }
+
+    fn lex(&mut self) -> Result<Token<'a>> {
+        self.skip_whitespace();
+        self.start = self.pos;
+
+        let bytes = self.input.as_bytes();
+        let Some(&b) = bytes.get(self.pos) else {
+            return Ok(Token::Eof);
+        };
+        let next = bytes.get(self.pos + 1).copied();
+
+        let punctuation = match b {
+            b'[' => Token::LeftBracket,
+            b']' => Token::RightBracket,
+            b'{' => Token::LeftBrace,
+            b'}' => Token::RightBrace,
+            b'(' => Token::LeftParen,
+            b')' => Token::RightParen,
+            b'\\' => Token::Backslash,
+            b',' => Token::Comma,
+            b'=' if next == Some(b'>') => {
+                self.pos += 2;
+                return Ok(Token::FatComma);
+            }
+            b'=' => Token::Equals,
+            b';' => Token::Semicolon,
+            b'$' if next.is_some_and(is_word_start) => {
+                let start = self.pos;
+                self.pos += 1;
+                while self
+                    .input
+                    .as_bytes()
+                    .get(self.pos)
+                    .is_some_and(|&b| is_word(b))
+                {
+                    self.pos += 1;
+                }
+                return Ok(Token::Variable(&self.input[start..self.pos]));
+            }
+            b'\'' => {
+                self.pos += 1;
+                return self.single_quoted(None, b'\'');
+            }
+            b'"' => {
+                self.pos += 1;
+                return self.double_quoted();
+            }
+            b'0'..=b'9' => return self.number(),
+            b'-' if next.is_some_and(|c| c.is_ascii_digit()) => return self.number(),
+            b'-' if next.is_some_and(is_word_start) => return self.word(),
+            b if is_word_start(b) => return self.word(),
+            _ => {
+                let c = self.input[self.pos..].chars().next().unwrap_or_default();
+                return Err(self.error(format!("unexpected character `{c}`")));
+            }
+        };
+        self.pos += 1;
+
+        Ok(punctuation)
+    }
+
+    fn skip_whitespace(&mut self) {
+        let bytes = self.input.as_bytes();
+        while let Some(&b) = bytes.get(self.pos) {
+            if b.is_ascii_whitespace() {
+                self.pos += 1;
+            } else if b == b'#' {
+                self.pos =
+                    memchr::memchr(b'\n', &bytes[self.pos..]).map_or(bytes.len(), |i| self.pos + i);
+            } else {
+                break;
+            }
+        }
+    }
+
+    /// A bareword, possibly with a leading `-` like `-foo => 1`, or a `q` string.
+    fn word(&mut self) -> Result<Token<'a>> {
+        let bytes = self.input.as_bytes();
+        let start = self.pos;
+        self.pos += 1;
+        while bytes.get(self.pos).is_some_and(|&b| is_word(b)) {
+            self.pos += 1;
+        }
+
+        let word = &self.input[start..self.pos];
+        if word == "q" {
+            if let Some(&delim) = bytes.get(self.pos) {
+                let fat_comma = delim == b'=' && bytes.get(self.pos + 1) == Some(&b'>');
+                if PUNCTUATION.as_bytes().contains(&delim) && !fat_comma {
+                    self.pos += 1;
+                    return match paired_quote_delimiter(delim) {
+                        Some(close) => self.single_quoted(Some(delim), close),
+                        None => self.single_quoted(None, delim),
+                    };
+                }
+            }
+        }
+
+        Ok(Token::Bareword(word))
+    }
+
+    fn number(&mut self) -> Result<Token<'a>> {
+        let bytes = self.input.as_bytes();
+        let is_digit = |pos: usize| bytes.get(pos).is_some_and(|b| b.is_ascii_digit());
+        let skip_digits = |mut pos: usize| {
+            while bytes
+                .get(pos)
+                .is_some_and(|&b| b.is_ascii_digit() || b == b'_')
+            {
+                pos += 1;
+            }
+            pos
+        };
+
+        let start = self.pos;
+        let mut end = skip_digits(start + 1);
+        let mut float = false;
+        if bytes.get(end) == Some(&b'.') && is_digit(end + 1) {
+            float = true;
+            end = skip_digits(end + 1);
+        }
+        if matches!(bytes.get(end), Some(b'e' | b'E')) {
+            let mut exp = end + 1;
+            if matches!(bytes.get(exp), Some(b'+' | b'-')) {
+                exp += 1;
+            }
+            if is_digit(exp) {
+                float = true;
+                end = skip_digits(exp);
+            }
+        }
+        self.pos = end;
+
+        let text = &self.input[start..end];
+        let text = if text.contains('_') {
+            Cow::Owned(text.replace('_', ""))
+        } else {
+            Cow::Borrowed(text)
+        };
+        if !float {
+            // Like Perl, fall back to a float for integers that don't fit in an i64.
+            if let Ok(i) = text.parse() {
+                return Ok(Token::Int(i));
+            }
+        }
+        match text.parse() {
+            Ok(f) => Ok(Token::Float(f)),
+            Err(_) => Err(self.error(format!("invalid number `{text}`"))),
+        }
+    }
+
+    /// Lex the rest of a single-quoted string, up to and including the `close` delimiter. Only the
+    /// delimiters and the backslash itself can be escaped; any other backslash is kept as is. With
+    /// a paired delimiter like `q{...}`, nested pairs are part of the string.
+    fn single_quoted(&mut self, open: Option<u8>, close: u8) -> Result<Token<'a>> {
+        let bytes = self.input.as_bytes();
+        let mut owned: Option<String> = None;
+        let mut chunk_start = self.pos;
+        let mut depth = 0usize;
+
+        loop {
+            let rest = &bytes[self.pos..];
+            let found = match open {
+                Some(open) => memchr3(b'\\', open, close, rest),
+                None => memchr2(b'\\', close, rest),
+            };
+            let Some(at) = found.map(|i| self.pos + i) else {
+                return Err(self.eof());
+            };
+            self.pos = at + 1;
+
+            match bytes[at] {
+                b'\\' => match bytes.get(at + 1) {
+                    Some(&c) if c == b'\\' || c == close || Some(c) == open => {
+                        let s = owned.get_or_insert_with(String::new);
+                        s.push_str(&self.input[chunk_start..at]);
+                        s.push(char::from(c));
+                        self.pos = at + 2;
+                        chunk_start = self.pos;
+                    }
+                    _ => {}
+                },
+                b if b == close && depth > 0 => depth -= 1,
+                b if b == close => {
+                    let tail = &self.input[chunk_start..at];
+                    let s = match owned {
+                        Some(mut s) => {
+                            s.push_str(tail);
+                            Cow::Owned(s)
+                        }
+                        None => Cow::Borrowed(tail),
+                    };
+                    return Ok(Token::String(s));
+                }
+                _ => depth += 1,
+            }
+        }
+    }
+
+    /// Lex the rest of a double-quoted string. Variables are not interpolated.
+    ///
+    /// Like Perl, a string whose characters all fit in a byte is a byte string. One that spells
+    /// out a byte above ASCII with an escape is lexed as [`Token::Bytes`], since it most likely
+    /// holds binary data or encoded text rather than Latin-1.
+    fn double_quoted(&mut self) -> Result<Token<'a>> {
+        let bytes = self.input.as_bytes();
+        let mut owned: Option<String> = None;
+        let mut chunk_start = self.pos;
+        let mut high_byte = false;
+
+        loop {
+            let Some(at) = memchr2(b'\\', b'"', &bytes[self.pos..]).map(|i| self.pos + i) else {
+                return Err(self.eof());
+            };
+            if bytes[at] == b'"' {
+                let tail = &self.input[chunk_start..at];
+                self.pos = at + 1;
+                let s = match owned {
+                    Some(mut s) => {
+                        s.push_str(tail);
+                        if high_byte && s.chars().all(|c| c <= '\u{ff}') {
+                            return Ok(Token::Bytes(s.chars().map(|c| c as u8).collect()));
+                        }
+                        Cow::Owned(s)
+                    }
+                    None => Cow::Borrowed(tail),
+                };
+                return Ok(Token::String(s));
+            }
+
+            let s = owned.get_or_insert_with(String::new);
+            s.push_str(&self.input[chunk_start..at]);
+            self.pos = at + 1;
+            high_byte |= self.escape(s)?;
+            chunk_start = self.pos;
+        }
+    }
+
+    /// Decode the escape sequence after a backslash in a double-quoted string, returning whether
+    /// it was a numeric escape for a byte above ASCII.
+    fn escape(&mut self, s: &mut String) -> Result<bool> {
+        let Some(c) = self.input[self.pos..].chars().next() else {
+            return Err(self.eof());
+        };
+        self.pos += c.len_utf8();
+
+        let decoded = match c {
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'f' => '\x0C',
+            'v' => '\x0B',
+            'b' => '\x08',
+            'a' => '\x07',
+            'e' => '\x1B',
+            'z' => '\x1F',
+            '0'..='7' => {
+                let digits = self.take_while(self.pos - 1, 3, |b| matches!(b, b'0'..=b'7'));
+                self.code_point(digits, 8)?
+            }
+            'x' => {
+                if self.input[self.pos..].starts_with('{') {
+                    let Some(end) = self.input[self.pos..].find('}') else {
+                        return Err(self.eof());
+                    };
+                    let digits = &self.input[self.pos + 1..self.pos + end];
+                    self.pos += end + 1;
+                    self.code_point(digits.trim(), 16)?
+                } else {
+                    let digits = self.take_while(self.pos, 2, |b| b.is_ascii_hexdigit());
+                    self.code_point(digits, 16)?
+                }
+            }
+            // Any other escaped character, such as `\\`, `\"`, `\$` or `\@`, is itself.
+            c => c,
+        };
+        s.push(decoded);
+
+        Ok(matches!(c, '0'..='7' | 'x') && ('\u{80}'..='\u{ff}').contains(&decoded))
+    }
+
+    /// Consume up to `max` bytes matching `pred` starting at `start`, returning them.
+    fn take_while(&mut self, start: usize, max: usize, pred: impl Fn(u8) -> bool) -> &'a str {
+        let input: &'a str = self.input;
+        let len = input.as_bytes()[start..]
+            .iter()
+            .take(max)
+            .take_while(|&&b| pred(b))
+            .count();
+        self.pos = start + len;
+        &input[start..start + len]
+    }
+
+    fn code_point(&self, digits: &str, radix: u32) -> Result<char> {
+        if digits.is_empty() {
+            return Ok('\0');
+        }
+        u32::from_str_radix(digits, radix)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error(format!("invalid character escape `{digits}`")))
+    }
+}
+
+fn is_word_start(b: u8) -> bool {
+    b.is_ascii_alphabetic() || b == b'_'
+}
+
+fn is_word(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+fn paired_quote_delimiter(c: u8) -> Option<u8> {
+    match c {
+        b'(' => Some(b')'),
+        b'[' => Some(b']'),
+        b'{' => Some(b'}'),
+        b'<' => Some(b'>'),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lex_all(input: &str) -> Vec<Token<'_>> {
+        let mut lexer = Lexer::new(input);
+        let mut tokens = Vec::new();
+        loop {
+            match lexer.next_token().unwrap() {
+                Token::Eof => return tokens,
+                token => tokens.push(token),
+            }
+        }
+    }
+
+    fn lex_one(input: &str) -> Token<'_> {
+        Lexer::new(input).next_token().unwrap()
+    }
+
+    fn string(s: &str) -> Token<'_> {
+        Token::String(Cow::Borrowed(s))
+    }
+
+    #[test]
+    fn test_punctuation() {
+        let tokens = lex_all("[ { } ] \\ , =>");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::LeftBracket,
+                Token::LeftBrace,
+                Token::RightBrace,
+                Token::RightBracket,
+                Token::Backslash,
+                Token::Comma,
+                Token::FatComma,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_statement() {
+        let tokens = lex_all("$VAR1 = 1;");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Variable("$VAR1"),
+                Token::Equals,
+                Token::Int(1),
+                Token::Semicolon,
+            ]
+        );
+
+        let tokens = lex_all("do{\\(my $o = 1)}");
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Bareword("do"),
+                Token::LeftBrace,
+                Token::Backslash,
+                Token::LeftParen,
+                Token::Bareword("my"),
+                Token::Variable("$o"),
+                Token::Equals,
+                Token::Int(1),
+                Token::RightParen,
+                Token::RightBrace,
+            ]
+        );
+
+        let mut lexer = Lexer::starting_at("1; 2 ", 2);
+        assert_eq!(lexer.next_token().unwrap(), Token::Int(2));
+        assert_eq!(lexer.offset(), 4);
+        assert!(!lexer.exhausted());
+        assert_eq!(lexer.peek().unwrap(), &Token::Eof);
+        assert_eq!(lexer.offset(), 4);
+        assert!(lexer.exhausted());
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(lex_one("123"), Token::Int(123));
+        assert_eq!(lex_one("-42"), Token::Int(-42));
+        assert_eq!(lex_one("1_000"), Token::Int(1000));
+        assert_eq!(lex_one("123.456"), Token::Float(123.456));
+        assert_eq!(lex_one("1e3"), Token::Float(1000.0));
+        assert_eq!(lex_one("2.5E-1"), Token::Float(0.25));
+        assert_eq!(lex_one("99999999999999999999"), Token::Float(1e20));
+    }
+
+    #[test]
+    fn test_barewords() {
+        assert_eq!(lex_one("undef"), Token::Bareword("undef"));
+        assert_eq!(lex_one("-foo_1"), Token::Bareword("-foo_1"));
+        assert_eq!(lex_all("q => 1")[0], Token::Bareword("q"));
+        assert_eq!(lex_all("q=>1")[0], Token::Bareword("q"));
+    }
+
+    #[test]
+    fn test_single_quoted() {
+        assert_eq!(lex_one("'hello'"), string("hello"));
+        assert_eq!(lex_one("''"), string(""));
+        assert_eq!(lex_one(r"'it\'s'"), string("it's"));
+        assert_eq!(lex_one(r"'a \\ b \n'"), string(r"a \ b \n"));
+        assert!(matches!(
+            lex_one("'hello'"),
+            Token::String(Cow::Borrowed(_))
+        ));
+    }
+
+    #[test]
+    fn test_q_string() {
+        assert_eq!(lex_one("q{hello}"), string("hello"));
+        assert_eq!(lex_one("q{}"), string(""));
+        assert_eq!(lex_one("q!hello!"), string("hello"));
+        assert_eq!(lex_one("q{a {b} c}"), string("a {b} c"));
+        assert_eq!(lex_one(r"q(a \) b)"), string("a ) b"));
+    }
+
+    #[test]
+    fn test_double_quoted() {
+        assert_eq!(lex_one(r#""""#), string(""));
+        assert_eq!(lex_one(r#""hello""#), string("hello"));
+        assert_eq!(lex_one(r#""a\tb\n""#), string("a\tb\n"));
+        assert_eq!(lex_one(r#""\$x \@y \"z\"""#), string("$x @y \"z\""));
+        assert_eq!(lex_one(r#""\x41\x{263a}\101\0""#), string("A\u{263a}A\0"));
+        assert_eq!(
+            lex_one(r#""\xff\x{e9}\351 ok""#),
+            Token::Bytes(b"\xff\xe9\xe9 ok".to_vec())
+        );
+        assert_eq!(lex_one(r#""\xe9\x{263a}""#), string("\u{e9}\u{263a}"));
+    }
+
+    #[test]
+    fn test_comments() {
+        let tokens = lex_all("# a comment\n[ # another\n1 ]");
+        assert_eq!(
+            tokens,
+            vec![Token::LeftBracket, Token::Int(1), Token::RightBracket]
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut lexer = Lexer::new("[\n  'unterminated");
+        lexer.next_token().unwrap();
+        assert!(matches!(lexer.next_token(), Err(Error::Eof { .. })));
+
+        let mut lexer = Lexer::new("[\n  ^");
+        lexer.next_token().unwrap();
+        match lexer.next_token() {
+            Err(Error::Syntax { line, column, .. }) => assert_eq!((line, column), (2, 3)),
+            other => panic!("expected a syntax error, got {other:?}"),
+        }
+
+        assert!(Lexer::new(r#""\x{110000}""#).next_token().is_err());
+    }
+}
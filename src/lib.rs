@@ -1,9 +1,42 @@
+//! Serde support for Perl data, as Data::Dumper writes it.
+//!
+//! [`from_str`] and its siblings read Perl source into Rust types, and [`parse`] into a
+//! [`Scalar`] tree. [`to_string`] writes Rust types back out as Perl.
+//!
+//! # Booleans
+//!
+//! Perl has no boolean literals, so `true` and `false` are written as `1` and `0`. Those read
+//! back as booleans, even with [`strict_booleans`]. Earlier versions wrote the barewords `true`
+//! and `false`, which Perl does not read as booleans at all.
+//!
+//! [`strict_booleans`]: Deserializer::strict_booleans
+
+#[macro_use]
+mod macros;
+
+mod bignum;
+pub mod blessed;
 mod de;
 mod error;
+mod lexer;
+mod maybe;
 mod parser;
+mod perl_ref;
 mod quote;
+mod registry;
 mod ser;
+pub mod value;
 
-pub use de::{from_perl, from_str, Deserializer};
+pub use bignum::BigNumber;
+pub use blessed::{Blessed, PerlClass};
+pub use de::{
+    from_perl, from_reader, from_reader_with_encoding, from_slice, from_slice_with_encoding,
+    from_str, Deserializer, Encoding, IoRead, StrRead, StreamDeserializer,
+};
 pub use error::{Error, Result};
+pub use maybe::Maybe;
+pub use parser::{parse, parse_borrowed, parse_interned, KeyInterner};
+pub use perl_ref::PerlRef;
+pub use registry::{ClassRegistry, ClassSeed};
 pub use ser::{to_string, Serializer};
+pub use value::{from_value, to_value, Scalar};
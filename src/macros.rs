@@ -0,0 +1,157 @@
+/// Builds a [`Scalar`](crate::Scalar) from Perl-like syntax.
+///
+/// Hashes are written `{ KEY => VALUE, ... }` and arrays `[ VALUE, ... ]`, both giving a
+/// reference like they do in Perl. A key that is a single identifier is a bareword; any other
+/// key is a Rust expression that is turned into a string. `undef` is undefined,
+/// `bless(VALUE, CLASS)` an object, and, since Rust does not allow a backslash outside of a
+/// string, `ref VALUE` a scalar reference. Anything else is a Rust expression converted with
+/// [`Scalar::from`](crate::Scalar), so variables and function calls can be interpolated.
+#[macro_export]
+macro_rules! perl {
+    (undef) => {
+        $crate::Scalar::Undefined
+    };
+    ([ $($tt:tt)* ]) => {
+        $crate::perl_internal!(@array [] $($tt)*)
+    };
+    ({ $($tt:tt)* }) => {
+        $crate::perl_internal!(@hash [] $($tt)*)
+    };
+    (ref $($tt:tt)+) => {
+        $crate::Scalar::scalar_ref($crate::perl!($($tt)+))
+    };
+    (bless ( $($tt:tt)+ )) => {
+        $crate::perl_internal!(@bless () $($tt)+)
+    };
+    ($other:expr) => {
+        $crate::Scalar::from($other)
+    };
+}
+
+/// Splits the contents of the brackets in [`perl!`] at their commas and `=>`s.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! perl_internal {
+    // Arrays: each element is everything up to the next comma.
+    (@array [$($items:expr,)*]) => {
+        {
+            let items: ::std::vec::Vec<$crate::Scalar> = ::std::vec![$($items,)*];
+            $crate::Scalar::array(items)
+        }
+    };
+    (@array [$($items:expr,)*] $($rest:tt)+) => {
+        $crate::perl_internal!(@item [$($items,)*] () $($rest)+)
+    };
+    (@item [$($items:expr,)*] ($($item:tt)+) , $($rest:tt)*) => {
+        $crate::perl_internal!(@array [$($items,)* $crate::perl!($($item)+),] $($rest)*)
+    };
+    (@item [$($items:expr,)*] ($($item:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::perl_internal!(@item [$($items,)*] ($($item)* $next) $($rest)*)
+    };
+    (@item [$($items:expr,)*] ($($item:tt)+)) => {
+        $crate::perl_internal!(@array [$($items,)* $crate::perl!($($item)+),])
+    };
+
+    // Hashes: a key is everything up to the next `=>`, and its value everything up to the next
+    // comma.
+    (@hash [$($entries:expr,)*]) => {
+        {
+            let entries: ::std::vec::Vec<($crate::value::Key, $crate::Scalar)> =
+                ::std::vec![$($entries,)*];
+            $crate::Scalar::hash(entries)
+        }
+    };
+    (@hash [$($entries:expr,)*] $($rest:tt)+) => {
+        $crate::perl_internal!(@key [$($entries,)*] () $($rest)+)
+    };
+    (@key [$($entries:expr,)*] ($($key:tt)+) => $($rest:tt)*) => {
+        $crate::perl_internal!(@value [$($entries,)*] ($($key)+) () $($rest)*)
+    };
+    (@key [$($entries:expr,)*] ($($key:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::perl_internal!(@key [$($entries,)*] ($($key)* $next) $($rest)*)
+    };
+    (@value [$($entries:expr,)*] ($($key:tt)+) ($($value:tt)+) , $($rest:tt)*) => {
+        $crate::perl_internal!(
+            @hash [$($entries,)* $crate::perl_internal!(@entry ($($key)+) ($($value)+)),]
+            $($rest)*
+        )
+    };
+    (@value [$($entries:expr,)*] ($($key:tt)+) ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::perl_internal!(@value [$($entries,)*] ($($key)+) ($($value)* $next) $($rest)*)
+    };
+    (@value [$($entries:expr,)*] ($($key:tt)+) ($($value:tt)+)) => {
+        $crate::perl_internal!(
+            @hash [$($entries,)* $crate::perl_internal!(@entry ($($key)+) ($($value)+)),]
+        )
+    };
+    (@entry ($key:ident) ($($value:tt)+)) => {
+        ($crate::value::Key::from(::std::stringify!($key)), $crate::perl!($($value)+))
+    };
+    (@entry ($($key:tt)+) ($($value:tt)+)) => {
+        (
+            $crate::value::Key::from(::std::string::ToString::to_string(&($($key)+))),
+            $crate::perl!($($value)+),
+        )
+    };
+
+    // Objects: the value is everything up to the first comma, and the class comes after it.
+    (@bless ($($value:tt)+) , $class:expr $(,)?) => {
+        $crate::Scalar::object($class, $crate::perl!($($value)+))
+    };
+    (@bless ($($value:tt)*) $next:tt $($rest:tt)*) => {
+        $crate::perl_internal!(@bless ($($value)* $next) $($rest)*)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_str, parse, to_string, Scalar};
+
+    #[test]
+    fn test_perl() {
+        let name = "Firefox";
+        let versions = vec![1, 2];
+        let scalar = perl!({
+            a => 42,
+            b => [1, -2.5, undef, [], {}],
+            c => ref "x",
+            "d e" => bless({ name => name }, "My::Class"),
+            (1 + 1) => versions.len() as i64,
+            versions => versions,
+            nested => { list => [true, ref ref undef,], },
+        });
+        let mut expected = parse(
+            "{ a => 42, b => [1, -2.5, undef, [], {}], c => \\'x', \
+               'd e' => bless( { name => 'Firefox' }, 'My::Class' ), \
+               2 => 2, versions => [1, 2], nested => { list => [1, \\\\undef] } }",
+        )
+        .unwrap();
+        expected["nested"]["list"][0] = Scalar::Bool(true);
+        assert_eq!(scalar, expected);
+
+        assert_eq!(perl!(undef), Scalar::Undefined);
+        assert_eq!(perl!("x"), parse("'x'").unwrap());
+        assert_eq!(perl!([[1], [2, 3]]), parse("[[1], [2, 3]]").unwrap());
+        assert_eq!(perl!(bless([], "X")), parse("bless( [], 'X' )").unwrap());
+    }
+
+    #[test]
+    fn test_perl_round_trip() {
+        let scalar = perl!([true, { flag => false }]);
+        let output = to_string(&scalar).unwrap();
+        assert_eq!(output, "[1,{flag=>0}]");
+        assert_eq!(
+            parse(&output).unwrap(),
+            parse("[1, { flag => 0 }]").unwrap()
+        );
+
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Flag {
+            flag: bool,
+        }
+        assert_eq!(
+            from_str::<(bool, Flag)>(&output).unwrap(),
+            (true, Flag { flag: false })
+        );
+    }
+}
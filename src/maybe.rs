@@ -0,0 +1,170 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// A hash value that may be missing, present but `undef`, or present with a value: the three
+/// answers Perl gives to `exists $h->{x}` and `defined $h->{x}` between them.
+///
+/// A missing key is only told apart from `undef` if the field falls back to its default when it
+/// is missing, so mark `Maybe` fields `#[serde(default)]`. Otherwise serde deserializes a missing
+/// field as if it were `undef`.
+///
+/// When serializing, [`Maybe::Absent`] is written like `None`, so either skip it with
+/// `#[serde(skip_serializing_if = "Maybe::is_absent")]` or have the [`Serializer`] omit `None`
+/// fields, while [`Maybe::Undefined`] is written as `undef` either way.
+///
+/// [`Serializer`]: crate::Serializer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Maybe<T> {
+    #[default]
+    Absent,
+    Undefined,
+    Defined(T),
+}
+
+impl<T> Maybe<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Maybe::Absent)
+    }
+
+    pub fn is_undefined(&self) -> bool {
+        matches!(self, Maybe::Undefined)
+    }
+
+    pub fn is_defined(&self) -> bool {
+        matches!(self, Maybe::Defined(_))
+    }
+
+    /// The value, if there is one.
+    pub fn defined(self) -> Option<T> {
+        match self {
+            Maybe::Defined(value) => Some(value),
+            Maybe::Absent | Maybe::Undefined => None,
+        }
+    }
+}
+
+/// `None` is absent and `Some(None)` is `undef`.
+impl<T> From<Option<Option<T>>> for Maybe<T> {
+    fn from(value: Option<Option<T>>) -> Self {
+        match value {
+            None => Maybe::Absent,
+            Some(None) => Maybe::Undefined,
+            Some(Some(value)) => Maybe::Defined(value),
+        }
+    }
+}
+
+impl<T> From<Maybe<T>> for Option<Option<T>> {
+    fn from(value: Maybe<T>) -> Self {
+        match value {
+            Maybe::Absent => None,
+            Maybe::Undefined => Some(None),
+            Maybe::Defined(value) => Some(Some(value)),
+        }
+    }
+}
+
+impl<T> Serialize for Maybe<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Maybe::Absent => serializer.serialize_none(),
+            Maybe::Undefined => serializer.serialize_unit(),
+            Maybe::Defined(value) => serializer.serialize_some(value),
+        }
+    }
+}
+
+impl<'de, T> Deserialize<'de> for Maybe<T>
+where
+    T: Deserialize<'de>,
+{
+    /// Only a value that is there gets deserialized, so it is never absent.
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Ok(match Option::deserialize(deserializer)? {
+            None => Maybe::Undefined,
+            Some(value) => Maybe::Defined(value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_perl, from_str, parser, to_string};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Config {
+        #[serde(default)]
+        timeout: Maybe<u32>,
+        #[serde(default)]
+        retries: Maybe<u32>,
+        #[serde(default)]
+        verbose: Maybe<bool>,
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let expected = Config {
+            timeout: Maybe::Absent,
+            retries: Maybe::Undefined,
+            verbose: Maybe::Defined(true),
+        };
+        let input = "{ retries => undef, verbose => 1 }";
+        assert_eq!(from_str::<Config>(input).unwrap(), expected);
+        assert_eq!(
+            from_perl::<Config>(&parser::parse(input).unwrap()).unwrap(),
+            expected
+        );
+
+        let values: Vec<Maybe<i32>> = from_str("[ undef, 1, \\undef ]").unwrap();
+        assert_eq!(
+            values,
+            [Maybe::Undefined, Maybe::Defined(1), Maybe::Undefined]
+        );
+    }
+
+    #[test]
+    fn test_serialize() {
+        let config = Config {
+            timeout: Maybe::Absent,
+            retries: Maybe::Undefined,
+            verbose: Maybe::Defined(false),
+        };
+        assert_eq!(
+            to_string(&config).unwrap(),
+            "{timeout=>undef,retries=>undef,verbose=>0}"
+        );
+
+        // Without omitting them, absent fields come back as `undef`.
+        assert_eq!(
+            from_str::<Config>(&to_string(&config).unwrap()).unwrap(),
+            Config {
+                timeout: Maybe::Undefined,
+                ..config
+            }
+        );
+
+        let mut serializer = crate::Serializer::new().omit_none_fields(true);
+        config.serialize(&mut serializer).unwrap();
+        let output = serializer.into_string();
+        assert_eq!(output, "{retries=>undef,verbose=>0}");
+        assert_eq!(from_str::<Config>(&output).unwrap(), config);
+    }
+
+    #[test]
+    fn test_option_conversions() {
+        for value in [None, Some(None), Some(Some(1))] {
+            assert_eq!(Option::from(Maybe::from(value)), value);
+        }
+        assert_eq!(Maybe::Defined(1).defined(), Some(1));
+        assert_eq!(Maybe::<i32>::Undefined.defined(), None);
+    }
+}
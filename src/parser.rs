@@ -1,294 +1,764 @@
-#[allow(unused_imports)]
-use nom::{
-    branch::alt,
-    bytes::complete::{escaped, tag},
-    character::complete::{char, digit1, multispace0, multispace1},
-    combinator::{map, map_res, opt},
-    multi::{many0, separated_list0},
-    sequence::{delimited, pair, preceded, terminated, tuple},
-    IResult,
+use serde::{ser::SerializeStruct, Serialize, Serializer};
+use std::{
+    borrow::{Borrow, Cow},
+    collections::{HashMap, HashSet},
+    fmt,
+    hash::Hasher,
+    ops::Deref,
+    sync::Arc,
 };
-use nom::{
-    bytes::complete::{escaped_transform, take_while1},
-    character::complete::{none_of, one_of},
-    combinator::value,
-    error::ErrorKind,
-    multi::many1,
-    AsChar, InputTakeAtPosition,
+
+use crate::{
+    blessed,
+    de::Encoding,
+    error::Result,
+    lexer::{Lexer, Token},
+    perl_ref,
+    quote::{float_quote, int_quote},
 };
-use std::collections::HashMap;
 
-/// These are all the characters that can be used as delimiters in Perl's `q` operator, I think.
-/// There might be more, and possibly unicode characters, but I don't need those for now.
-const PUNCTUATION: &str = r##"!"#$%&'(*+,-/:;<=?@[\^`{|~"##;
+/// Parse a scalar that owns all of its strings.
+pub fn parse(input: &str) -> Result<Scalar<'static>> {
+    let mut parser = Parser::new(input);
+    let scalar = parser.parse_scalar_owned()?;
+    parser.end()?;
+    Ok(scalar)
+}
 
-pub fn parse(input: &str) -> crate::error::Result<Scalar> {
-    let (_, scalar) = parse_scalar(input).map_err(|e| crate::error::Error::Nom(format!("{e}")))?;
+/// Parse a scalar that owns all of its strings, sharing hash keys through `interner`.
+///
+/// Dumps of many records repeat the same keys over and over; with an interner each distinct key
+/// is allocated once, however many hashes use it, and once across all the dumps parsed with the
+/// same interner.
+pub fn parse_interned(input: &str, interner: &mut KeyInterner) -> Result<Scalar<'static>> {
+    let mut parser = Parser::new(input);
+    let scalar = parser.parse_scalar_with(&|s| Cow::Owned(s.into_owned()), &mut |key| {
+        Key::Shared(interner.intern(&key))
+    })?;
+    parser.end()?;
     Ok(scalar)
 }
 
-#[derive(Debug, Clone, PartialEq)]
-pub enum Scalar {
+/// Parse a scalar whose strings and hash keys borrow from `input` wherever they contain no escape
+/// sequences.
+pub fn parse_borrowed(input: &str) -> Result<Scalar<'_>> {
+    let mut parser = Parser::new(input);
+    let scalar = parser.parse_scalar()?;
+    parser.end()?;
+    Ok(scalar)
+}
+
+/// Read a byte string as text: as UTF-8 if it is valid UTF-8, which is what a byte string of text
+/// usually holds, and otherwise as Latin-1, like Perl.
+pub fn bytes_to_text(bytes: &[u8]) -> Cow<'_, str> {
+    Encoding::Utf8
+        .decode(bytes)
+        .or_else(|_| Encoding::Latin1.decode(bytes))
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Scalar<'a> {
+    #[default]
     Undefined,
     Int(i64),
     Float(f64),
-    String(String),
-    Reference(Box<Reference>),
+    String(Cow<'a, str>),
+    /// A string of bytes rather than characters. See [`bytes_to_text`] for reading one as text.
+    Bytes(Cow<'a, [u8]>),
+    Reference(Box<Reference<'a>>),
+    /// A reference blessed into a class, written `bless( ..., 'Class' )`.
+    Object(Box<Object<'a>>),
+    /// A boolean object from one of the JSON modules, which bless a reference to `1` or `0` into
+    /// one of the [`BOOLEAN_CLASSES`].
+    Bool(bool),
+}
+
+/// The classes that Perl's JSON modules bless their `true` and `false` into.
+pub const BOOLEAN_CLASSES: &[&str] = &[
+    "JSON::PP::Boolean",
+    "Types::Serialiser::Boolean",
+    "JSON::XS::Boolean",
+    "Cpanel::JSON::XS::Boolean",
+];
+
+pub(crate) fn is_boolean_class(class: &str) -> bool {
+    BOOLEAN_CLASSES.contains(&class)
+}
+
+/// The boolean that an object of `class` blessing `value` stands for, if it is one.
+fn boolean(class: &str, value: &Scalar) -> Option<bool> {
+    if !is_boolean_class(class) {
+        return None;
+    }
+    match value {
+        Scalar::Reference(reference) => match &**reference {
+            Reference::Scalar(scalar) if !scalar.is_nested() => Some(scalar.is_true()),
+            _ => None,
+        },
+        _ => None,
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct Hash(pub HashMap<String, Scalar>);
+pub struct Hash<'a>(pub HashMap<Key<'a>, Scalar<'a>>);
 
 #[derive(Debug, Clone, PartialEq)]
-pub struct Array(pub Vec<Scalar>);
+pub struct Array<'a>(pub Vec<Scalar<'a>>);
 
+/// The target of a reference. [`Scalar::Reference`] boxes this once, so a container costs one
+/// allocation for itself on top of its contents.
 #[derive(Debug, Clone, PartialEq)]
-pub enum Reference {
-    Hash(Box<Hash>),
-    Array(Box<Array>),
-    Scalar(Box<Scalar>),
+pub enum Reference<'a> {
+    Hash(Hash<'a>),
+    Array(Array<'a>),
+    Scalar(Scalar<'a>),
 }
 
-fn parse_scalar(input: &str) -> IResult<&str, Scalar> {
-    alt((parse_literal_scalar, parse_reference))(input)
+/// A blessed reference: the name of the package it was blessed into, and the reference itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object<'a> {
+    pub class: Cow<'a, str>,
+    pub value: Scalar<'a>,
 }
 
-fn parse_reference(input: &str) -> IResult<&str, Scalar> {
-    let (input, reference) = alt((parse_hashref, parse_arrayref, parse_scalarref))(input)?;
-
-    Ok((input, Scalar::Reference(Box::new(reference))))
+/// A hash key: borrowed from the input, owned, or shared with other hashes through a
+/// [`KeyInterner`]. Keys compare and hash like the strings they hold.
+#[derive(Clone)]
+pub enum Key<'a> {
+    Borrowed(&'a str),
+    Owned(Box<str>),
+    Shared(Arc<str>),
 }
 
-fn parse_scalarref(input: &str) -> IResult<&str, Reference> {
-    let (input, _) = char('\\')(input)?;
-    let (input, scalar) = parse_scalar(input)?;
-
-    Ok((input, Reference::Scalar(Box::new(scalar))))
+impl Key<'_> {
+    pub fn into_owned(self) -> Key<'static> {
+        match self {
+            Key::Borrowed(key) => Key::Owned(key.into()),
+            Key::Owned(key) => Key::Owned(key),
+            Key::Shared(key) => Key::Shared(key),
+        }
+    }
 }
 
-fn parse_hashref(input: &str) -> IResult<&str, Reference> {
-    let (input, _) = char('{')(input)?;
-    let (input, _) = multispace0(input)?;
-    let (input, pairs) = separated_list0(comma, parse_pair)(input)?;
-    let (input, _) = opt(comma)(input)?;
-    let (input, _) = multispace0(input)?;
-    let (input, _) = char('}')(input)?;
+impl Deref for Key<'_> {
+    type Target = str;
 
-    let mut hash = HashMap::new();
-    for (key, value) in pairs {
-        if let Scalar::String(key) = key {
-            hash.insert(key, value);
-        } else {
-            return Err(nom::Err::Error(nom::error::Error::new(
-                input,
-                ErrorKind::Char,
-            )));
+    fn deref(&self) -> &str {
+        match self {
+            Key::Borrowed(key) => key,
+            Key::Owned(key) => key,
+            Key::Shared(key) => key,
         }
     }
+}
+
+impl Borrow<str> for Key<'_> {
+    fn borrow(&self) -> &str {
+        self
+    }
+}
 
-    Ok((input, Reference::Hash(Box::new(Hash(hash)))))
+impl PartialEq for Key<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
 }
 
-fn parse_pair(input: &str) -> IResult<&str, (Scalar, Scalar)> {
-    alt((parse_fatcomma_pair, parse_comma_pair))(input)
+impl Eq for Key<'_> {}
+
+impl std::hash::Hash for Key<'_> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (**self).hash(state)
+    }
 }
 
-fn parse_comma_pair(input: &str) -> IResult<&str, (Scalar, Scalar)> {
-    let (input, _) = multispace0(input)?;
-    let (input, key) = parse_literal_scalar(input)?;
-    let (input, _) = comma(input)?;
-    let (input, value) = parse_scalar(input)?;
+impl fmt::Debug for Key<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
 
-    Ok((input, (key, value)))
+impl<'a> From<&'a str> for Key<'a> {
+    fn from(key: &'a str) -> Self {
+        Key::Borrowed(key)
+    }
 }
 
-fn parse_fatcomma_pair(input: &str) -> IResult<&str, (Scalar, Scalar)> {
-    let (input, _) = multispace0(input)?;
-    let (input, key) = parse_bareword_or_literal(input)?;
-    let (input, _) = multispace0(input)?;
-    let (input, _) = tag("=>")(input)?;
-    let (input, _) = multispace0(input)?;
-    let (input, value) = parse_scalar(input)?;
+impl From<String> for Key<'_> {
+    fn from(key: String) -> Self {
+        Key::Owned(key.into_boxed_str())
+    }
+}
 
-    Ok((input, (key, value)))
+impl<'a> From<Cow<'a, str>> for Key<'a> {
+    fn from(key: Cow<'a, str>) -> Self {
+        match key {
+            Cow::Borrowed(key) => Key::Borrowed(key),
+            Cow::Owned(key) => key.into(),
+        }
+    }
 }
 
-fn parse_bareword_or_literal(input: &str) -> IResult<&str, Scalar> {
-    alt((parse_bareword, parse_literal_scalar))(input)
+impl<'a> From<Key<'a>> for Cow<'a, str> {
+    fn from(key: Key<'a>) -> Self {
+        match key {
+            Key::Borrowed(key) => Cow::Borrowed(key),
+            Key::Owned(key) => Cow::Owned(key.into()),
+            Key::Shared(key) => Cow::Owned(key.to_string()),
+        }
+    }
 }
 
-fn parse_bareword(input: &str) -> IResult<&str, Scalar> {
-    let (input, s) = take_while1(|c: char| c.is_ascii_alphanumeric() || c == '_')(input)?;
+impl Serialize for Key<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self)
+    }
+}
 
-    Ok((input, Scalar::String(s.to_string())))
+/// Hands out one shared allocation per distinct hash key. See [`parse_interned`].
+#[derive(Debug, Default)]
+pub struct KeyInterner {
+    keys: HashSet<Arc<str>>,
 }
 
-fn comma(input: &str) -> IResult<&str, char> {
-    delimited(multispace0, char(','), multispace0)(input)
+impl KeyInterner {
+    pub fn new() -> Self {
+        KeyInterner::default()
+    }
+
+    pub fn intern(&mut self, key: &str) -> Arc<str> {
+        if let Some(shared) = self.keys.get(key) {
+            return shared.clone();
+        }
+        let shared = Arc::<str>::from(key);
+        self.keys.insert(shared.clone());
+        shared
+    }
+
+    /// How many distinct keys have been interned.
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
 }
 
-/* [ "foo", 1.0, 2, undef, ] */
-fn parse_arrayref(input: &str) -> IResult<&str, Reference> {
-    let (input, _) = char('[')(input)?;
-    let (input, _) = multispace0(input)?;
-    let (input, scalars) = separated_list0(comma, parse_scalar)(input)?;
-    let (input, _) = opt(comma)(input)?;
-    let (input, _) = multispace0(input)?;
-    let (input, _) = char(']')(input)?;
+impl<'a> Scalar<'a> {
+    /// Whether dropping this scalar could recurse.
+    fn is_nested(&self) -> bool {
+        matches!(self, Scalar::Reference(_) | Scalar::Object(_))
+    }
 
-    Ok((input, Reference::Array(Box::new(Array(scalars)))))
+    /// Whether Perl considers this scalar true: anything but `undef`, zero, `''` and `'0'`.
+    pub fn is_true(&self) -> bool {
+        match self {
+            Scalar::Undefined => false,
+            Scalar::Int(i) => *i != 0,
+            Scalar::Float(f) => *f != 0.0,
+            Scalar::String(s) => !s.is_empty() && s != "0",
+            Scalar::Bytes(b) => !b.is_empty() && **b != *b"0",
+            Scalar::Bool(b) => *b,
+            Scalar::Reference(_) | Scalar::Object(_) => true,
+        }
+    }
 }
 
-fn parse_literal_scalar(input: &str) -> IResult<&str, Scalar> {
-    let (input, _) = multispace0(input)?;
-    alt((parse_undef, parse_number, parse_string))(input)
+impl<'a> Reference<'a> {
+    /// Move any nested references out of this one and onto `stack`, leaving it shallow.
+    fn take_children(&mut self, stack: &mut Vec<Scalar<'a>>) {
+        let is_ref = Scalar::is_nested;
+        match self {
+            Reference::Hash(hash) => {
+                stack.extend(hash.0.drain().map(|(_, value)| value).filter(is_ref));
+            }
+            Reference::Array(array) => {
+                stack.extend(array.0.drain(..).filter(is_ref));
+            }
+            Reference::Scalar(scalar) => {
+                if is_ref(scalar) {
+                    stack.push(std::mem::replace(scalar, Scalar::Undefined));
+                }
+            }
+        }
+    }
 }
 
-fn parse_undef(input: &str) -> IResult<&str, Scalar> {
-    let (input, _) = tag("undef")(input)?;
+// The derived drop glue would recurse once per level of nesting, which overflows the stack on
+// deeply nested data. Flatten the tree onto a heap-allocated stack instead.
+impl Drop for Reference<'_> {
+    fn drop(&mut self) {
+        let mut stack = Vec::new();
+        self.take_children(&mut stack);
+        drop_flattened(stack);
+    }
+}
 
-    Ok((input, Scalar::Undefined))
+impl Drop for Object<'_> {
+    fn drop(&mut self) {
+        if self.value.is_nested() {
+            drop_flattened(vec![std::mem::replace(&mut self.value, Scalar::Undefined)]);
+        }
+    }
 }
 
-fn parse_string(input: &str) -> IResult<&str, Scalar> {
-    alt((
-        parse_single_quoted_string,
-        parse_double_quoted_string,
-        parse_q_string,
-    ))(input)
+/// Drop the scalars on `stack`, moving their children onto it first so that none of them
+/// recurses.
+fn drop_flattened(mut stack: Vec<Scalar<'_>>) {
+    while let Some(scalar) = stack.pop() {
+        match scalar {
+            Scalar::Reference(mut reference) => reference.take_children(&mut stack),
+            Scalar::Object(mut object) if object.value.is_nested() => {
+                stack.push(std::mem::replace(&mut object.value, Scalar::Undefined));
+            }
+            _ => {}
+        }
+    }
 }
 
-fn perl_digit1(input: &str) -> IResult<&str, &str> {
-    input.split_at_position1_complete(|item| !is_perl_digit(item), ErrorKind::Digit)
+impl Serialize for Scalar<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Scalar::Undefined => serializer.serialize_unit(),
+            Scalar::Int(i) => serializer.serialize_i64(*i),
+            Scalar::Float(f) => serializer.serialize_f64(*f),
+            Scalar::String(s) => serializer.serialize_str(s),
+            Scalar::Bytes(b) => serializer.serialize_bytes(b),
+            Scalar::Bool(b) => serializer.serialize_bool(*b),
+            Scalar::Reference(r) => match &**r {
+                Reference::Hash(h) => serializer.collect_map(&h.0),
+                Reference::Array(a) => serializer.collect_seq(&a.0),
+                Reference::Scalar(s) => serializer.serialize_newtype_struct(perl_ref::NAME, s),
+            },
+            // Objects are written the way a `Blessed` is.
+            Scalar::Object(o) => {
+                let mut object =
+                    serializer.serialize_struct(blessed::NAME, blessed::FIELDS.len())?;
+                object.serialize_field(blessed::FIELDS[0], &o.value)?;
+                object.serialize_field(blessed::FIELDS[1], &*o.class)?;
+                object.end()
+            }
+        }
+    }
 }
 
-fn is_perl_digit(c: char) -> bool {
-    c.is_dec_digit() || c == '_'
+/// A container that has been opened but not yet closed.
+enum Frame<'a> {
+    Array(Vec<Scalar<'a>>),
+    /// The hash built so far and the key of the value being parsed.
+    Hash(HashMap<Key<'a>, Scalar<'a>>, Key<'a>),
+    ScalarRef,
+    /// The inside of `do{\(my $o = ...)}`, which is how Data::Dumper writes a scalar reference
+    /// that is blessed.
+    ScalarRefBlock,
+    /// The inside of `bless( ..., 'Class' )`.
+    Object,
 }
 
-fn parse_number(input: &str) -> IResult<&str, Scalar> {
-    let (input, parts) = tuple((
-        opt(char('-')),
-        perl_digit1,
-        opt(tuple((char('.'), many1(perl_digit1)))),
-        opt(preceded(one_of("eE"), digit1)),
-    ))(input)?;
+#[derive(Clone)]
+pub struct Parser<'a> {
+    lexer: Lexer<'a>,
+}
 
-    match parts {
-        (sign, int, None, e) => {
-            let sign = sign.map(|c| c.to_string()).unwrap_or_default();
-            let s = format!("{}{}{}", sign, int, e.unwrap_or(""));
-            let s = s.replace('_', "");
-            let i = s.parse::<i64>().unwrap();
-            Ok((input, Scalar::Int(i)))
+impl<'a> Parser<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Parser {
+            lexer: Lexer::new(input),
         }
-        (sign, int, Some((_, frac)), e) => {
-            let sign = sign.map(|c| c.to_string()).unwrap_or_default();
-            let s = format!("{}{}.{}{}", sign, int, frac.join(""), e.unwrap_or(""));
-            let s = s.replace('_', "");
-            let f = s.parse::<f64>().unwrap();
-            Ok((input, Scalar::Float(f)))
+    }
+
+    /// A parser that starts `offset` bytes into `input`.
+    pub fn starting_at(input: &'a str, offset: usize) -> Self {
+        Parser {
+            lexer: Lexer::starting_at(input, offset),
+        }
+    }
+
+    /// The byte offset just past the last token consumed.
+    pub fn offset(&self) -> usize {
+        self.lexer.offset()
+    }
+
+    /// Whether the parser has looked at all of its input.
+    pub fn exhausted(&self) -> bool {
+        self.lexer.exhausted()
+    }
+
+    pub fn peek(&mut self) -> Result<&Token<'a>> {
+        self.lexer.peek()
+    }
+
+    pub fn next_token(&mut self) -> Result<Token<'a>> {
+        self.lexer.next_token()
+    }
+
+    /// Check that nothing but whitespace and comments is left in the input.
+    pub fn end(&mut self) -> Result<()> {
+        match self.lexer.next_token()? {
+            Token::Eof => Ok(()),
+            token => Err(self.lexer.unexpected(&token, "end of input")),
+        }
+    }
+
+    /// Skip the `$VAR1 =` that Data::Dumper writes before a value, if there is one.
+    pub fn assignment(&mut self) -> Result<()> {
+        if let Token::Variable(_) = self.lexer.peek()? {
+            self.lexer.next_token()?;
+            match self.lexer.next_token()? {
+                Token::Equals => {}
+                token => return Err(self.lexer.unexpected(&token, "`=`")),
+            }
+        }
+        Ok(())
+    }
+
+    /// Skip the `;` that ends a Data::Dumper statement, if there is one. Errors in whatever
+    /// follows are left for the next parse to report.
+    pub fn statement_end(&mut self) {
+        if matches!(self.lexer.peek(), Ok(Token::Semicolon)) {
+            // Taking a token that has already been peeked can't fail.
+            let _ = self.lexer.next_token();
         }
     }
-}
 
-fn parse_single_quoted_string(input: &str) -> IResult<&str, Scalar> {
-    let (input, s) = delimited(
-        char('\''),
-        opt(escaped(none_of("\\'"), '\\', one_of("'\\"))),
-        char('\''),
-    )(input)?;
-
-    Ok((input, Scalar::String(s.unwrap_or_default().to_string())))
-}
-
-fn parse_double_quoted_string(input: &str) -> IResult<&str, Scalar> {
-    let (input, s) = delimited(
-        char('"'),
-        opt(escaped_transform(
-            none_of("\\\""),
-            '\\',
-            alt((
-                value("\\", tag("\\")),
-                value("\"", tag("\"")),
-                value("\n", tag("n")),
-                value("\r", tag("r")),
-                value("\t", tag("t")),
-                value("\0", tag("0")),
-                value("\x0B", tag("v")),
-                value("\x08", tag("b")),
-                value("\x07", tag("a")),
-                value("\x1B", tag("e")),
-                value("\x1F", tag("z")),
-            )),
-        )),
-        char('"'),
-    )(input)?;
-
-    Ok((input, Scalar::String(s.unwrap_or_default().to_string())))
-}
-
-/// this parses:
-/// - q(foo)
-/// - q{foo}
-/// - q[foo]
-/// - q<foo>
-/// - q"foo"
-/// - q'foo'
-/// - q!foo!
-/// - q@foo@
-/// etc
-fn parse_q_string(input: &str) -> IResult<&str, Scalar> {
-    let (input, _) = char('q')(input)?;
-    // delim is any char that is not a letter, digit, or underscore
-    let (input, start_delim) = one_of(PUNCTUATION)(input)?;
-    let end_delim = paired_quote_delimiter(start_delim);
-    let esc = format!("{}\\", end_delim);
-    let (input, s) = opt(escaped(
-        many0(none_of(esc.as_str())),
-        '\\',
-        one_of(esc.as_str()),
-    ))(input)?;
-    let (input, _) = char(end_delim)(input)?;
-
-    Ok((input, Scalar::String(s.unwrap_or_default().to_string())))
-}
-
-fn paired_quote_delimiter(c: char) -> char {
-    match c {
-        '(' => ')',
-        '[' => ']',
-        '{' => '}',
-        '<' => '>',
-        c => c,
+    /// Parse the scalar that starts with `token`, which must not be a container or reference.
+    pub fn parse_literal(&mut self, token: Token<'a>) -> Result<Scalar<'a>> {
+        self.literal(token, &|s| s)
     }
+
+    /// Parse a single scalar and throw it away, without building it.
+    pub fn skip_scalar(&mut self) -> Result<()> {
+        // Whatever is open, without its contents.
+        let mut stack = Vec::new();
+
+        loop {
+            match self.lexer.next_token()? {
+                Token::Backslash => continue,
+                Token::LeftBracket => {
+                    if self.lexer.peek()? == &Token::RightBracket {
+                        self.lexer.next_token()?;
+                    } else {
+                        stack.push(Frame::Array(Vec::new()));
+                        continue;
+                    }
+                }
+                Token::LeftBrace => {
+                    if self.lexer.peek()? == &Token::RightBrace {
+                        self.lexer.next_token()?;
+                    } else {
+                        self.parse_key()?;
+                        stack.push(Frame::Hash(HashMap::new(), Key::Borrowed("")));
+                        continue;
+                    }
+                }
+                token if self.object_start(&token)? => {
+                    stack.push(Frame::Object);
+                    continue;
+                }
+                token if self.scalar_ref_start(&token)? => {
+                    stack.push(Frame::ScalarRefBlock);
+                    continue;
+                }
+                token => {
+                    self.parse_literal(token)?;
+                }
+            }
+
+            loop {
+                match stack.pop() {
+                    None => return Ok(()),
+                    Some(frame @ Frame::Array(_))
+                        if self.list_separator(Token::RightBracket)? =>
+                    {
+                        stack.push(frame);
+                        break;
+                    }
+                    Some(frame @ Frame::Hash(..)) if self.list_separator(Token::RightBrace)? => {
+                        self.parse_key()?;
+                        stack.push(frame);
+                        break;
+                    }
+                    Some(Frame::ScalarRefBlock) => self.scalar_ref_end()?,
+                    Some(Frame::Object) => {
+                        self.object_end()?;
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+
+    /// Parse a single scalar, borrowing strings from the input where possible.
+    pub fn parse_scalar(&mut self) -> Result<Scalar<'a>> {
+        self.parse_scalar_with(&|s| s, &mut Key::from)
+    }
+
+    /// Parse a single scalar, copying every string out of the input.
+    pub fn parse_scalar_owned(&mut self) -> Result<Scalar<'static>> {
+        self.parse_scalar_with(&|s| Cow::Owned(s.into_owned()), &mut |key| {
+            Key::from(key).into_owned()
+        })
+    }
+
+    /// Parse a single scalar, including arbitrarily nested references, passing each string
+    /// through `string` and each hash key through `key`.
+    ///
+    /// Open containers are kept on an explicit stack rather than the call stack, so the nesting
+    /// depth of the input is limited only by available memory.
+    fn parse_scalar_with<'b, F, K>(&mut self, string: &F, key: &mut K) -> Result<Scalar<'b>>
+    where
+        F: Fn(Cow<'a, str>) -> Cow<'b, str>,
+        K: FnMut(Cow<'a, str>) -> Key<'b>,
+    {
+        let mut stack = Vec::new();
+
+        loop {
+            let mut scalar = match self.lexer.next_token()? {
+                Token::Backslash => {
+                    stack.push(Frame::ScalarRef);
+                    continue;
+                }
+                Token::LeftBracket => {
+                    if self.lexer.peek()? == &Token::RightBracket {
+                        self.lexer.next_token()?;
+                        array_ref(Vec::new())
+                    } else {
+                        stack.push(Frame::Array(Vec::new()));
+                        continue;
+                    }
+                }
+                Token::LeftBrace => {
+                    if self.lexer.peek()? == &Token::RightBrace {
+                        self.lexer.next_token()?;
+                        hash_ref(HashMap::new())
+                    } else {
+                        let key = key(self.parse_key()?);
+                        stack.push(Frame::Hash(HashMap::new(), key));
+                        continue;
+                    }
+                }
+                token if self.object_start(&token)? => {
+                    stack.push(Frame::Object);
+                    continue;
+                }
+                token if self.scalar_ref_start(&token)? => {
+                    stack.push(Frame::ScalarRefBlock);
+                    continue;
+                }
+                token => self.literal(token, string)?,
+            };
+
+            // Fold the finished scalar into its enclosing containers, closing each one that ends
+            // here.
+            loop {
+                match stack.pop() {
+                    None => return Ok(scalar),
+                    Some(Frame::ScalarRef) => {
+                        scalar = Scalar::Reference(Box::new(Reference::Scalar(scalar)));
+                    }
+                    Some(Frame::ScalarRefBlock) => {
+                        self.scalar_ref_end()?;
+                        scalar = Scalar::Reference(Box::new(Reference::Scalar(scalar)));
+                    }
+                    Some(Frame::Object) => {
+                        let class = string(self.object_end()?);
+                        scalar = match boolean(&class, &scalar) {
+                            Some(b) => Scalar::Bool(b),
+                            None => Scalar::Object(Box::new(Object {
+                                class,
+                                value: scalar,
+                            })),
+                        };
+                    }
+                    Some(Frame::Array(mut items)) => {
+                        items.push(scalar);
+                        if self.list_separator(Token::RightBracket)? {
+                            stack.push(Frame::Array(items));
+                            break;
+                        }
+                        scalar = array_ref(items);
+                    }
+                    Some(Frame::Hash(mut hash, k)) => {
+                        hash.insert(k, scalar);
+                        if self.list_separator(Token::RightBrace)? {
+                            let k = key(self.parse_key()?);
+                            stack.push(Frame::Hash(hash, k));
+                            break;
+                        }
+                        scalar = hash_ref(hash);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Turn a token that starts a value into a scalar, if it is a complete value on its own.
+    fn literal<'b, F>(&mut self, token: Token<'a>, string: &F) -> Result<Scalar<'b>>
+    where
+        F: Fn(Cow<'a, str>) -> Cow<'b, str>,
+    {
+        match token {
+            Token::Int(i) => Ok(Scalar::Int(i)),
+            Token::Float(f) => Ok(Scalar::Float(f)),
+            Token::String(s) => Ok(Scalar::String(string(s))),
+            Token::Bytes(b) => Ok(Scalar::Bytes(Cow::Owned(b))),
+            // The fat comma quotes the word to its left, even in a list.
+            Token::Bareword(word) if self.lexer.peek()? == &Token::FatComma => {
+                Ok(Scalar::String(string(Cow::Borrowed(word))))
+            }
+            Token::Bareword("undef") => Ok(Scalar::Undefined),
+            token => Err(self.lexer.unexpected(&token, "a value")),
+        }
+    }
+
+    /// Parse a hash key and the `=>` or `,` that separates it from its value, borrowing the key
+    /// from the input where possible. Perl stringifies numeric keys.
+    pub fn parse_key(&mut self) -> Result<Cow<'a, str>> {
+        let key = match self.lexer.next_token()? {
+            Token::String(s) => s,
+            Token::Bytes(b) => Cow::Owned(bytes_to_text(&b).into_owned()),
+            Token::Bareword(word) => Cow::Borrowed(word),
+            Token::Int(i) => {
+                let mut key = String::new();
+                int_quote(&mut key, i);
+                Cow::Owned(key)
+            }
+            Token::Float(f) if f.is_finite() => {
+                let mut key = String::new();
+                float_quote(&mut key, f);
+                Cow::Owned(key)
+            }
+            token => return Err(self.lexer.unexpected(&token, "a hash key")),
+        };
+        match self.lexer.next_token()? {
+            Token::FatComma | Token::Comma => Ok(key),
+            token => Err(self.lexer.unexpected(&token, "`=>`")),
+        }
+    }
+
+    /// Take the `(` after `token` if it is the `bless` that starts an object, saying whether it
+    /// was.
+    pub fn object_start(&mut self, token: &Token<'a>) -> Result<bool> {
+        if token != &Token::Bareword("bless") || self.lexer.peek()? != &Token::LeftParen {
+            return Ok(false);
+        }
+        self.lexer.next_token()?;
+        Ok(true)
+    }
+
+    /// Parse the `, 'Class' )` that ends an object, returning the class.
+    pub fn object_end(&mut self) -> Result<Cow<'a, str>> {
+        self.expect(Token::Comma)?;
+        let class = match self.lexer.next_token()? {
+            Token::String(s) => s,
+            Token::Bytes(b) => Cow::Owned(bytes_to_text(&b).into_owned()),
+            token => return Err(self.lexer.unexpected(&token, "a class name")),
+        };
+        if self.lexer.peek()? == &Token::Comma {
+            self.lexer.next_token()?;
+        }
+        self.expect(Token::RightParen)?;
+        Ok(class)
+    }
+
+    /// Take the `{\(my $o =` after `token` if it is the `do` that starts a reference to a
+    /// scalar variable, saying whether it was.
+    pub fn scalar_ref_start(&mut self, token: &Token<'a>) -> Result<bool> {
+        if token != &Token::Bareword("do") || self.lexer.peek()? != &Token::LeftBrace {
+            return Ok(false);
+        }
+        self.lexer.next_token()?;
+        self.expect(Token::Backslash)?;
+        self.expect(Token::LeftParen)?;
+        self.expect(Token::Bareword("my"))?;
+        match self.lexer.next_token()? {
+            Token::Variable(_) => {}
+            token => return Err(self.lexer.unexpected(&token, "a variable")),
+        }
+        self.expect(Token::Equals)?;
+        Ok(true)
+    }
+
+    /// Parse the `)}` that ends a reference to a scalar variable.
+    pub fn scalar_ref_end(&mut self) -> Result<()> {
+        self.expect(Token::RightParen)?;
+        self.expect(Token::RightBrace)
+    }
+
+    fn expect(&mut self, expected: Token<'_>) -> Result<()> {
+        match self.lexer.next_token()? {
+            token if token == expected => Ok(()),
+            token => Err(self.lexer.unexpected(&token, &expected.to_string())),
+        }
+    }
+
+    /// Parse what follows a list element: either a comma and another element (`true`), or an
+    /// optional trailing comma and the `close` delimiter (`false`).
+    pub fn list_separator(&mut self, close: Token<'_>) -> Result<bool> {
+        match self.lexer.next_token()? {
+            Token::Comma | Token::FatComma => {
+                if self.lexer.peek()? == &close {
+                    self.lexer.next_token()?;
+                    Ok(false)
+                } else {
+                    Ok(true)
+                }
+            }
+            token if token == close => Ok(false),
+            token => Err(self.lexer.unexpected(&token, &format!("`,` or {close}"))),
+        }
+    }
+}
+
+pub(crate) fn array_ref(items: Vec<Scalar<'_>>) -> Scalar<'_> {
+    Scalar::Reference(Box::new(Reference::Array(Array(items))))
+}
+
+pub(crate) fn hash_ref<'a>(hash: HashMap<Key<'a>, Scalar<'a>>) -> Scalar<'a> {
+    Scalar::Reference(Box::new(Reference::Hash(Hash(hash))))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::Error;
+
+    fn parse_ref(input: &str) -> Reference<'static> {
+        match parse(input).unwrap() {
+            Scalar::Reference(reference) => *reference,
+            other => panic!("expected a reference, got {other:?}"),
+        }
+    }
 
     #[test]
     fn test_parse_undef() {
         let input = "undef";
         let expected = Scalar::Undefined;
-        let actual = parse_undef(input).unwrap().1;
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
     }
 
     #[test]
     fn test_parse_string() {
         let input = "'hello'";
-        let expected = Scalar::String("hello".to_string());
-        let actual = parse_single_quoted_string(input).unwrap().1;
+        let expected = Scalar::String("hello".into());
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
     }
 
     #[test]
     fn test_parse_q_string() {
         let input = "q{hello}";
-        let expected = Scalar::String("hello".to_string());
-        let actual = parse_q_string(input).unwrap().1;
+        let expected = Scalar::String("hello".into());
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
     }
 
@@ -296,138 +766,339 @@ mod tests {
     fn test_parse_literal_scalar() {
         let input = "undef";
         let expected = Scalar::Undefined;
-        let actual = parse_literal_scalar(input).unwrap().1;
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
 
         let input = "123";
         let expected = Scalar::Int(123);
-        let actual = parse_literal_scalar(input).unwrap().1;
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
 
         let input = "123.456";
         let expected = Scalar::Float(123.456);
-        let actual = parse_literal_scalar(input).unwrap().1;
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
 
         let input = "'hello'";
-        let expected = Scalar::String("hello".to_string());
-        let actual = parse_literal_scalar(input).unwrap().1;
+        let expected = Scalar::String("hello".into());
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
 
         let input = "q{hello}";
-        let expected = Scalar::String("hello".to_string());
-        let actual = parse_literal_scalar(input).unwrap().1;
+        let expected = Scalar::String("hello".into());
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
     }
 
     #[test]
     fn test_parse_pair() {
-        let input = "'foo'=>123";
-        let expected = (Scalar::String("foo".to_string()), Scalar::Int(123));
-        let actual = parse_pair(input).unwrap().1;
+        let input = "{'foo'=>123}";
+        let expected = Reference::Hash(Hash(
+            vec![("foo".into(), Scalar::Int(123))].into_iter().collect(),
+        ));
+        let actual = parse_ref(input);
         assert_eq!(expected, actual);
     }
 
     #[test]
     fn test_hashref() {
         let input = "{ 'foo' => 'bar' }";
-        let expected = Reference::Hash(Box::new(Hash(
-            vec![("foo".to_string(), Scalar::String("bar".to_string()))]
+        let expected = Reference::Hash(Hash(
+            vec![("foo".into(), Scalar::String("bar".into()))]
                 .into_iter()
                 .collect(),
-        )));
-        let actual = parse_hashref(input).unwrap().1;
+        ));
+        let actual = parse_ref(input);
         assert_eq!(expected, actual);
     }
 
     #[test]
     fn test_arrayref() {
         let input = "[ 'foo', 'bar' ]";
-        let expected = Reference::Array(Box::new(Array(vec![
-            Scalar::String("foo".to_string()),
-            Scalar::String("bar".to_string()),
-        ])));
-        let actual = parse_arrayref(input).unwrap().1;
+        let expected = Reference::Array(Array(vec![
+            Scalar::String("foo".into()),
+            Scalar::String("bar".into()),
+        ]));
+        let actual = parse_ref(input);
         assert_eq!(expected, actual);
     }
 
     #[test]
     fn test_scalarref() {
         let input = "\\123";
-        let expected = Reference::Scalar(Box::new(Scalar::Int(123)));
-        let actual = parse_scalarref(input).unwrap().1;
+        let expected = Reference::Scalar(Scalar::Int(123));
+        let actual = parse_ref(input);
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    fn test_object() {
+        let input = "bless( { name => 'x' }, 'My::Class' )";
+        let Scalar::Object(object) = parse(input).unwrap() else {
+            panic!("expected an object");
+        };
+        assert_eq!(object.class, "My::Class");
+        assert_eq!(object.value, parse("{ name => 'x' }").unwrap());
+
+        let input = "bless( do{\\(my $o = 1)}, 'My::Counter' )";
+        let Scalar::Object(object) = parse(input).unwrap() else {
+            panic!("expected an object");
+        };
+        assert_eq!(object.class, "My::Counter");
+        assert_eq!(object.value, parse("\\1").unwrap());
+
+        assert_eq!(
+            parse("{ bless => 1, do => 2 }").unwrap(),
+            parse("{ 'bless' => 1, 'do' => 2 }").unwrap()
+        );
+        assert!(parse("bless( [], )").is_err());
+        assert!(parse("do{\\(our $o = 1)}").is_err());
+    }
+
+    #[test]
+    fn test_json_booleans() {
+        let input = "[ bless( do{\\(my $o = 1)}, 'JSON::PP::Boolean' ), \
+                       bless( do{\\(my $o = 0)}, 'Types::Serialiser::Boolean' ), \
+                       bless( \\'', 'Cpanel::JSON::XS::Boolean' ) ]";
+        let expected = array_ref(vec![
+            Scalar::Bool(true),
+            Scalar::Bool(false),
+            Scalar::Bool(false),
+        ]);
+        assert_eq!(parse(input).unwrap(), expected);
+        assert_eq!(parse_borrowed(input).unwrap(), expected);
+
+        // They are written as the numbers that read back as them.
+        let output = crate::to_string(&expected).unwrap();
+        assert_eq!(output, "[1,0,0]");
+        let reparsed = parse(&output).unwrap();
+        assert_eq!(
+            crate::from_perl::<Vec<bool>>(&reparsed).unwrap(),
+            [true, false, false]
+        );
+
+        // Only a reference to a plain scalar is a boolean.
+        let input = "bless( [1], 'JSON::PP::Boolean' )";
+        assert!(matches!(parse(input).unwrap(), Scalar::Object(_)));
+    }
+
     #[test]
     fn test_array_trailing_comma() {
         let input = "[ 'foo', 'bar', ]";
-        let expected = Reference::Array(Box::new(Array(vec![
-            Scalar::String("foo".to_string()),
-            Scalar::String("bar".to_string()),
-        ])));
-        let actual = parse_arrayref(input).unwrap().1;
+        let expected = Reference::Array(Array(vec![
+            Scalar::String("foo".into()),
+            Scalar::String("bar".into()),
+        ]));
+        let actual = parse_ref(input);
         assert_eq!(expected, actual);
     }
 
     #[test]
     fn test_hash_trailing_comma() {
         let input = "{ 'foo' => 'bar', }";
-        let expected = Reference::Hash(Box::new(Hash(
-            vec![("foo".to_string(), Scalar::String("bar".to_string()))]
+        let expected = Reference::Hash(Hash(
+            vec![("foo".into(), Scalar::String("bar".into()))]
                 .into_iter()
                 .collect(),
-        )));
-        let actual = parse_hashref(input).unwrap().1;
+        ));
+        let actual = parse_ref(input);
         assert_eq!(expected, actual);
     }
 
     #[test]
     fn test_array_of_hash() {
         let input = "[ { 'foo' => 'bar' }, { 'baz' => 'qux' } ]";
-        parse_arrayref(input).unwrap();
+        parse_ref(input);
     }
 
     #[test]
     fn test_deeply_nested() {
         let input = "{ 'foo' => [ 'bar', { 'baz' => 'qux' } ] }";
 
-        let actual = parse_hashref(input).unwrap().1;
-        let foo = "foo".to_string();
-        let bar = "bar".to_string();
-        let baz = "baz".to_string();
-        let qux = Scalar::String("qux".to_string());
-        let bazqux = Hash(vec![(baz, qux)].into_iter().collect());
-        let barbazqux = Array(vec![
-            Scalar::String(bar),
-            Scalar::Reference(Box::new(Reference::Hash(Box::new(bazqux)))),
-        ]);
-        let expected = Reference::Hash(Box::new(Hash(
-            vec![(
-                foo,
-                Scalar::Reference(Box::new(Reference::Array(Box::new(barbazqux)))),
-            )]
+        let actual = parse(input).unwrap();
+        let expected = perl!({ foo => ["bar", { baz => "qux" }] });
+        assert_eq!(expected, actual);
+    }
+
+    #[test]
+    fn test_very_deeply_nested() {
+        let depth = 500_000;
+        let input = format!("{}1{}", "[{a=>\\".repeat(depth), "}]".repeat(depth));
+        let scalar = parse(&input).unwrap();
+
+        let mut levels = 0;
+        let mut cursor = &scalar;
+        while let Scalar::Reference(reference) = cursor {
+            cursor = match &**reference {
+                Reference::Array(array) => &array.0[0],
+                Reference::Hash(hash) => &hash.0["a"],
+                Reference::Scalar(scalar) => scalar,
+            };
+            levels += 1;
+        }
+        assert_eq!(levels, 3 * depth);
+        assert_eq!(cursor, &Scalar::Int(1));
+
+        let input = format!("{}1{}", "bless(\\".repeat(depth), ",'A')".repeat(depth));
+        drop(parse(&input).unwrap());
+    }
+
+    #[test]
+    fn test_parse_number() {
+        let cases = [
+            ("1_000", Scalar::Int(1000)),
+            ("-42", Scalar::Int(-42)),
+            ("1e3", Scalar::Float(1000.0)),
+            ("2.5E-1", Scalar::Float(0.25)),
+            ("99999999999999999999", Scalar::Float(1e20)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse(input).unwrap(), expected, "{input}");
+        }
+
+        assert!(parse("_").is_err());
+        assert!(parse("-_").is_err());
+    }
+
+    #[test]
+    fn test_single_quote_escapes() {
+        let input = r"'it\'s a \\ and a \n'";
+        let expected = Scalar::String(r"it's a \ and a \n".into());
+        assert_eq!(parse(input).unwrap(), expected);
+
+        let input = r"q{a \} b}";
+        let expected = Scalar::String("a } b".into());
+        assert_eq!(parse(input).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_keys_and_barewords() {
+        let input = "{ 1 => 'a', -b => 'c', d, 'e' }";
+        let expected = Reference::Hash(Hash(
+            vec![
+                ("1".into(), Scalar::String("a".into())),
+                ("-b".into(), Scalar::String("c".into())),
+                ("d".into(), Scalar::String("e".into())),
+            ]
             .into_iter()
             .collect(),
-        )));
-        assert_eq!(expected, actual);
+        ));
+        assert_eq!(parse_ref(input), expected);
+
+        let input = "[ foo => 1 ]";
+        let expected = Reference::Array(Array(vec![Scalar::String("foo".into()), Scalar::Int(1)]));
+        assert_eq!(parse_ref(input), expected);
+
+        assert!(parse("[ foo, 1 ]").is_err());
+    }
+
+    #[test]
+    fn test_errors() {
+        assert!(matches!(parse("[ 1, 2"), Err(Error::Eof { .. })));
+        match parse("{ a => 1 }\n  ]") {
+            Err(Error::Syntax { line, column, .. }) => assert_eq!((line, column), (2, 3)),
+            other => panic!("expected a syntax error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_borrowed() {
+        let input = r#"{ plain => 'hello', escaped => "a\tb" }"#;
+        let Scalar::Reference(reference) = parse_borrowed(input).unwrap() else {
+            panic!("expected a reference");
+        };
+        let Reference::Hash(hash) = &*reference else {
+            panic!("expected a hash");
+        };
+
+        let (key, plain) = hash.0.get_key_value("plain").unwrap();
+        assert!(matches!(key, Key::Borrowed("plain")));
+        assert!(matches!(plain, Scalar::String(Cow::Borrowed("hello"))));
+        assert!(matches!(&hash.0["escaped"], Scalar::String(Cow::Owned(s)) if s == "a\tb"));
+    }
+
+    #[test]
+    fn test_parse_interned() {
+        let input = "[ { id => 1, 'name' => 'a' }, { id => 2, \"name\" => 'b' } ]";
+        let mut interner = KeyInterner::new();
+        let scalar = parse_interned(input, &mut interner).unwrap();
+        assert_eq!(scalar, parse(input).unwrap());
+        assert_eq!(interner.len(), 2);
+
+        let Scalar::Reference(reference) = &scalar else {
+            panic!("expected a reference");
+        };
+        let Reference::Array(records) = &**reference else {
+            panic!("expected an array");
+        };
+        let keys: Vec<_> = records
+            .0
+            .iter()
+            .map(|record| match record {
+                Scalar::Reference(reference) => match &**reference {
+                    Reference::Hash(hash) => match hash.0.get_key_value("name") {
+                        Some((Key::Shared(key), _)) => key.clone(),
+                        other => panic!("expected a shared key, got {other:?}"),
+                    },
+                    other => panic!("expected a hash, got {other:?}"),
+                },
+                other => panic!("expected a reference, got {other:?}"),
+            })
+            .collect();
+        assert!(Arc::ptr_eq(&keys[0], &keys[1]));
+    }
+
+    #[test]
+    fn test_skip_scalar() {
+        let mut parser = Parser::new(
+            "[ { a => [1, \\'x'], b => {} }, [], bless( do{\\(my $o = 1)}, 'A' ), undef ] 42",
+        );
+        parser.skip_scalar().unwrap();
+        assert_eq!(parser.parse_scalar().unwrap(), Scalar::Int(42));
+        parser.end().unwrap();
+
+        assert!(Parser::new("[ { a => 1 ]").skip_scalar().is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input = r#"{ 'a\'b' => [ 1, -2.5, 1e400, 'c:\\dir\\', undef, \'x', "\xff\0" ] }"#;
+        let once = parse(&crate::to_string(&parse(input).unwrap()).unwrap()).unwrap();
+        let twice = parse(&crate::to_string(&once).unwrap()).unwrap();
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn test_object_round_trip() {
+        let input = "[ bless( { a => [1] }, 'My::Class' ), bless( \\'x', 'My::Ref' ) ]";
+        let scalar = parse(input).unwrap();
+        let output = crate::to_string(&scalar).unwrap();
+        assert_eq!(
+            output,
+            "[bless({a=>[1]},'My::Class'),bless(\\'x','My::Ref')]"
+        );
+        assert_eq!(parse(&output).unwrap(), scalar);
+
+        let blessed = crate::Blessed::new("My::Class", vec![1]);
+        let output = crate::to_string(&crate::to_value(&blessed).unwrap()).unwrap();
+        assert_eq!(output, "bless([1],'My::Class')");
     }
 
     #[test]
     fn test_empty_string() {
         let input = "''";
-        let expected = Scalar::String("".to_string());
-        let actual = parse_single_quoted_string(input).unwrap().1;
+        let expected = Scalar::String("".into());
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
 
         let input = "q{}";
-        let expected = Scalar::String("".to_string());
-        let actual = parse_q_string(input).unwrap().1;
+        let expected = Scalar::String("".into());
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
 
         let input = r#""""#;
-        let expected = Scalar::String("".to_string());
-        let actual = parse_double_quoted_string(input).unwrap().1;
+        let expected = Scalar::String("".into());
+        let actual = parse(input).unwrap();
         assert_eq!(expected, actual);
     }
 }
@@ -0,0 +1,183 @@
+use serde::{de::Visitor, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+/// The newtype struct name that tells this crate's serializer and deserializers they are dealing
+/// with a [`PerlRef`], which any other format sees as a plain newtype.
+pub(crate) const NAME: &str = "$serde_perl_dumper::private::PerlRef";
+
+/// A reference to a scalar, written `\VALUE`.
+///
+/// Everything else reads through scalar references and never writes them, so `\'foo'` and
+/// `'foo'` look the same. A `PerlRef` only reads a scalar reference, and writes one back.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct PerlRef<T>(pub T);
+
+impl<T> PerlRef<T> {
+    pub fn new(value: T) -> Self {
+        PerlRef(value)
+    }
+
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> From<T> for PerlRef<T> {
+    fn from(value: T) -> Self {
+        PerlRef(value)
+    }
+}
+
+impl<T> Deref for PerlRef<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for PerlRef<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> Serialize for PerlRef<T>
+where
+    T: Serialize,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_newtype_struct(NAME, &self.0)
+    }
+}
+
+impl<'de, T> Deserialize<'de> for PerlRef<T>
+where
+    T: Deserialize<'de>,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(NAME, PerlRefVisitor(PhantomData))
+    }
+}
+
+struct PerlRefVisitor<T>(PhantomData<T>);
+
+impl<'de, T> Visitor<'de> for PerlRefVisitor<T>
+where
+    T: Deserialize<'de>,
+{
+    type Value = PerlRef<T>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a scalar reference")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        T::deserialize(deserializer).map(PerlRef)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_perl, from_str, parser, to_string, Maybe};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Config {
+        name: PerlRef<String>,
+        size: u32,
+    }
+
+    #[test]
+    fn test_deserialize() {
+        let input = "{ name => \\'foo', size => \\1 }";
+        let expected = Config {
+            name: PerlRef("foo".to_owned()),
+            size: 1,
+        };
+        assert_eq!(from_str::<Config>(input).unwrap(), expected);
+        let scalar = parser::parse(input).unwrap();
+        assert_eq!(from_perl::<Config>(&scalar).unwrap(), expected);
+
+        let values: Vec<PerlRef<Option<i32>>> =
+            from_str("[ \\undef, do{\\(my $o = 2)}, \\\\3 ]").unwrap();
+        assert_eq!(values, [PerlRef(None), PerlRef(Some(2)), PerlRef(Some(3))]);
+        let nested: PerlRef<PerlRef<i32>> = from_str("\\\\3").unwrap();
+        assert_eq!(nested, PerlRef(PerlRef(3)));
+
+        for input in ["'foo'", "[1]", "{}", "bless( \\1, 'X' )"] {
+            assert!(from_str::<PerlRef<i32>>(input).is_err(), "{input}");
+            let scalar = parser::parse(input).unwrap();
+            assert!(from_perl::<PerlRef<i32>>(&scalar).is_err(), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_option() {
+        #[derive(Serialize, Deserialize, Debug, PartialEq)]
+        struct Limits {
+            soft: Option<PerlRef<i32>>,
+            #[serde(default)]
+            hard: Maybe<PerlRef<i32>>,
+        }
+
+        let input = "{ soft => \\1, hard => \\2 }";
+        let expected = Limits {
+            soft: Some(PerlRef(1)),
+            hard: Maybe::Defined(PerlRef(2)),
+        };
+        assert_eq!(from_str::<Limits>(input).unwrap(), expected);
+        let scalar = parser::parse(input).unwrap();
+        assert_eq!(from_perl::<Limits>(&scalar).unwrap(), expected);
+
+        let cases = [
+            ("{ soft => undef, hard => \\undef }", Maybe::Undefined),
+            ("{ soft => \\\\undef }", Maybe::Absent),
+        ];
+        for (input, hard) in cases {
+            let expected = Limits { soft: None, hard };
+            assert_eq!(from_str::<Limits>(input).unwrap(), expected, "{input}");
+            let scalar = parser::parse(input).unwrap();
+            assert_eq!(from_perl::<Limits>(&scalar).unwrap(), expected, "{input}");
+        }
+
+        // Only a reference will do inside the option, as it does outside one.
+        assert!(from_str::<Limits>("{ soft => 1 }").is_err());
+        assert!(from_str::<Option<PerlRef<i32>>>("1").is_err());
+
+        let output = to_string(&expected).unwrap();
+        assert_eq!(output, "{soft=>\\1,hard=>\\2}");
+        assert_eq!(from_str::<Limits>(&output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_serialize() {
+        let config = Config {
+            name: PerlRef("foo".to_owned()),
+            size: 1,
+        };
+        assert_eq!(to_string(&config).unwrap(), "{name=>\\'foo',size=>1}");
+        assert_eq!(to_string(&PerlRef(vec![PerlRef(1)])).unwrap(), "\\[\\1]");
+        assert_eq!(
+            from_str::<Config>(&to_string(&config).unwrap()).unwrap(),
+            config
+        );
+
+        // Scalar references in a parsed tree are written back as references.
+        let scalar = parser::parse("[ \\'a', \\\\undef ]").unwrap();
+        assert_eq!(to_string(&scalar).unwrap(), "[\\'a',\\\\undef]");
+    }
+}
@@ -4,9 +4,9 @@ pub fn single_quote(output: &mut String, value: &str) {
     output.push('\'');
     for c in value.chars() {
         match c {
-            '\'' => {
+            '\'' | '\\' => {
                 output.push('\\');
-                output.push('\'');
+                output.push(c);
             }
             _ => output.push(c),
         }
@@ -14,6 +14,28 @@ pub fn single_quote(output: &mut String, value: &str) {
     output.push('\'');
 }
 
+/// Write a double-quoted Perl byte string, escaping every byte that isn't printable ASCII.
+pub fn bytes_quote(output: &mut String, value: &[u8]) {
+    output.reserve(value.len() + 2);
+    output.push('"');
+    for &b in value {
+        match b {
+            b'"' | b'\\' | b'$' | b'@' => {
+                output.push('\\');
+                output.push(char::from(b));
+            }
+            b' '..=b'~' => output.push(char::from(b)),
+            _ => {
+                const HEX: &[u8; 16] = b"0123456789abcdef";
+                output.push_str("\\x");
+                output.push(char::from(HEX[usize::from(b >> 4)]));
+                output.push(char::from(HEX[usize::from(b & 0xf)]));
+            }
+        }
+    }
+    output.push('"');
+}
+
 /// quote a string if it contains any special characters
 /// This is ideal for keys on the left side of the fat-comma => operator
 pub fn bare_quote(output: &mut String, value: &str) {
@@ -33,12 +55,17 @@ where
     output.push_str(buffer.format(value));
 }
 
-pub fn float_quote<F>(output: &mut String, value: F)
-where
-    F: ryu::Float,
-{
-    let mut buffer = ryu::Buffer::new();
-    output.push_str(buffer.format(value));
+/// Perl has no literals for infinity or NaN, so those are written as the strings Perl would
+/// numify back into them, the same way Data::Dumper does.
+pub fn float_quote(output: &mut String, value: f64) {
+    if value.is_nan() {
+        single_quote(output, "NaN");
+    } else if value.is_infinite() {
+        single_quote(output, if value > 0.0 { "Inf" } else { "-Inf" });
+    } else {
+        let mut buffer = ryu::Buffer::new();
+        output.push_str(buffer.format_finite(value));
+    }
 }
 
 pub fn is_bareword(value: &str) -> bool {
@@ -61,5 +88,25 @@ mod tests {
         let mut output = String::new();
         super::single_quote(&mut output, "hello 'world'");
         assert_eq!(output, "'hello \\'world\\''");
+
+        let mut output = String::new();
+        super::single_quote(&mut output, r"C:\temp\");
+        assert_eq!(output, r"'C:\\temp\\'");
+    }
+
+    #[test]
+    fn test_bytes_quote() {
+        let mut output = String::new();
+        super::bytes_quote(&mut output, b"a\"$@\\\x00\xff\n");
+        assert_eq!(output, r#""a\"\$\@\\\x00\xff\x0a""#);
+    }
+
+    #[test]
+    fn test_float_quote() {
+        let mut output = String::new();
+        super::float_quote(&mut output, 1.5);
+        super::float_quote(&mut output, f64::INFINITY);
+        super::float_quote(&mut output, f64::NAN);
+        assert_eq!(output, "1.5'Inf''NaN'");
     }
 }
@@ -0,0 +1,321 @@
+use serde::de::{self, DeserializeOwned, DeserializeSeed, Visitor};
+use serde::Deserialize;
+use std::{any::Any, borrow::Cow, cell::Cell, collections::HashMap, fmt};
+
+use crate::de::{Deserializer, Options, ScalarDeserializer};
+use crate::error::{Error, Result};
+use crate::parser::Scalar;
+
+/// The newtype struct name that tells this crate's deserializers to hand their settings to a
+/// [`ClassSeed`] along with the object, which any other format sees as a plain newtype.
+pub(crate) const NAME: &str = "$serde_perl_dumper::private::ClassRegistry";
+
+thread_local! {
+    /// The settings of the deserializer handing an object to a [`ClassSeed`], which serde has
+    /// no way to pass along with it.
+    static OPTIONS: Cell<Option<Options>> = const { Cell::new(None) };
+}
+
+/// Hand the value `deserializer` is on to the visitor of a [`ClassSeed`], along with `options`.
+pub(crate) fn hand_over<'de, D, V>(
+    deserializer: D,
+    options: Options,
+    visitor: V,
+) -> Result<V::Value>
+where
+    D: de::Deserializer<'de, Error = Error>,
+    V: Visitor<'de>,
+{
+    OPTIONS.set(Some(options));
+    visitor.visit_newtype_struct(deserializer)
+}
+
+type Decoder<T> = Box<dyn Fn(ScalarDeserializer<'_>) -> Result<T> + Send + Sync>;
+
+/// Maps Perl classes to the Rust types their objects decode as, for dumps that mix objects of
+/// many classes.
+///
+/// Each class decodes to the same `T`: a `Box<dyn Any>` to downcast later, or an enum with a
+/// variant per class.
+pub struct ClassRegistry<T> {
+    decoders: HashMap<String, Decoder<T>>,
+}
+
+impl<T> ClassRegistry<T> {
+    pub fn new() -> Self {
+        ClassRegistry {
+            decoders: HashMap::new(),
+        }
+    }
+
+    /// Decode objects of `class` as `U`, then turn that into a `T` with `convert`, which may be
+    /// as simple as an enum variant.
+    pub fn register<U, F>(&mut self, class: &str, convert: F) -> &mut Self
+    where
+        U: DeserializeOwned,
+        F: Fn(U) -> T + Send + Sync + 'static,
+    {
+        self.register_hook(class, move |value| U::deserialize(value).map(&convert))
+    }
+
+    /// Decode objects of `class` with `hook`, which is given the reference that was blessed.
+    pub fn register_hook<F>(&mut self, class: &str, hook: F) -> &mut Self
+    where
+        F: Fn(ScalarDeserializer<'_>) -> Result<T> + Send + Sync + 'static,
+    {
+        self.decoders.insert(class.to_owned(), Box::new(hook));
+        self
+    }
+
+    pub fn is_registered(&self, class: &str) -> bool {
+        self.decoders.contains_key(class)
+    }
+
+    /// Decode an object as whatever its class is registered as.
+    pub fn from_perl(&self, scalar: &Scalar) -> Result<T> {
+        self.decode(scalar, Options::default())
+    }
+
+    /// Decode an object with the settings of the deserializer it came from.
+    fn decode(&self, scalar: &Scalar, options: Options) -> Result<T> {
+        let Scalar::Object(object) = scalar else {
+            return Err(<Error as serde::de::Error>::custom(
+                "expected a blessed reference",
+            ));
+        };
+        let decode = self.decoders.get(&*object.class).ok_or_else(|| {
+            <Error as serde::de::Error>::custom(format_args!(
+                "no type is registered for class `{}`",
+                object.class
+            ))
+        })?;
+        decode(ScalarDeserializer::with_options(
+            Cow::Borrowed(&object.value),
+            options,
+        ))
+    }
+
+    /// Decode the object that is the next value `deserializer` reads, with its settings.
+    pub fn deserialize(&self, deserializer: &mut Deserializer<'_>) -> Result<T> {
+        let scalar = deserializer.parse_scalar()?;
+        self.decode(&scalar, deserializer.options())
+    }
+
+    /// A seed that decodes the object that is the next value a deserializer reads, for objects
+    /// nested in something else: pass it to
+    /// [`next_element_seed`](de::SeqAccess::next_element_seed), or deserialize with it in a
+    /// `#[serde(deserialize_with = "...")]` function.
+    pub fn seed(&self) -> ClassSeed<'_, T> {
+        ClassSeed { registry: self }
+    }
+
+    /// Decode the object that is the whole of `input`.
+    pub fn from_str(&self, input: &str) -> Result<T> {
+        let mut deserializer = Deserializer::from_str(input);
+        let value = self.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(value)
+    }
+}
+
+impl ClassRegistry<Box<dyn Any>> {
+    /// Decode objects of `class` as `U`, boxed up to be downcast.
+    pub fn register_any<U>(&mut self, class: &str) -> &mut Self
+    where
+        U: DeserializeOwned + 'static,
+    {
+        self.register(class, |value: U| Box::new(value) as Box<dyn Any>)
+    }
+}
+
+/// Decodes an object with a [`ClassRegistry`], wherever a deserializer comes across it.
+pub struct ClassSeed<'a, T> {
+    registry: &'a ClassRegistry<T>,
+}
+
+impl<'de, T> DeserializeSeed<'de> for ClassSeed<'_, T> {
+    type Value = T;
+
+    fn deserialize<D>(self, deserializer: D) -> std::result::Result<T, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(NAME, self)
+    }
+}
+
+impl<'de, T> Visitor<'de> for ClassSeed<'_, T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a blessed reference")
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> std::result::Result<T, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        // Other formats have no settings to hand over, nor objects to decode.
+        let options = OPTIONS.take().unwrap_or_default();
+        let scalar = Scalar::deserialize(deserializer)?;
+        self.registry
+            .decode(&scalar, options)
+            .map_err(de::Error::custom)
+    }
+}
+
+impl<T> Default for ClassRegistry<T> {
+    fn default() -> Self {
+        ClassRegistry::new()
+    }
+}
+
+impl<T> fmt::Debug for ClassRegistry<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.decoders.keys()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_perl, from_str, parser};
+    use std::sync::OnceLock;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Bug {
+        id: u32,
+        #[serde(deserialize_with = "object")]
+        assignee: Box<Object>,
+        #[serde(deserialize_with = "object")]
+        product: Box<Object>,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct User {
+        login: String,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Object {
+        Bug(Bug),
+        User(User),
+        Product(String),
+    }
+
+    /// An element of an array of objects of any class.
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct Item(#[serde(deserialize_with = "object")] Box<Object>);
+
+    fn registry() -> ClassRegistry<Object> {
+        let mut registry = ClassRegistry::new();
+        registry
+            .register("Bugzilla::Bug", Object::Bug)
+            .register("Bugzilla::User", Object::User)
+            .register_hook("Bugzilla::Product", |value| {
+                let name: HashMap<String, String> = Deserialize::deserialize(value)?;
+                Ok(Object::Product(name["name"].to_uppercase()))
+            });
+        registry
+    }
+
+    fn object<'de, D>(deserializer: D) -> std::result::Result<Box<Object>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        static REGISTRY: OnceLock<ClassRegistry<Object>> = OnceLock::new();
+        let registry = REGISTRY.get_or_init(registry);
+        registry.seed().deserialize(deserializer).map(Box::new)
+    }
+
+    fn user(login: &str) -> Box<Object> {
+        Box::new(Object::User(User {
+            login: login.to_owned(),
+        }))
+    }
+
+    const BUG: &str = "bless( { id => 1, \
+                               assignee => bless( { login => 'a' }, 'Bugzilla::User' ), \
+                               product => bless( { name => 'x' }, 'Bugzilla::Product' ) }, \
+                             'Bugzilla::Bug' )";
+
+    fn bug() -> Object {
+        Object::Bug(Bug {
+            id: 1,
+            assignee: user("a"),
+            product: Box::new(Object::Product("X".to_owned())),
+        })
+    }
+
+    #[test]
+    fn test_enum() {
+        let registry = registry();
+        assert_eq!(registry.from_str(BUG).unwrap(), bug());
+        assert_eq!(
+            registry.from_perl(&parser::parse(BUG).unwrap()).unwrap(),
+            bug()
+        );
+    }
+
+    #[test]
+    fn test_nested() {
+        let input = format!("[ {BUG}, bless( {{ login => 'b' }}, 'Bugzilla::User' ) ]");
+        let expected = [Item(Box::new(bug())), Item(user("b"))];
+        assert_eq!(from_str::<Vec<Item>>(&input).unwrap(), expected);
+        let scalar = parser::parse(&input).unwrap();
+        assert_eq!(from_perl::<Vec<Item>>(&scalar).unwrap(), expected);
+
+        // Nested objects are decoded with the settings of the deserializer they are in.
+        let input = "[ bless( { login => 7 }, 'Bugzilla::User' ) ]";
+        assert!(from_str::<Vec<Item>>(input).is_err());
+        let mut deserializer = Deserializer::from_str(input).coerce(true);
+        let items = Vec::<Item>::deserialize(&mut deserializer).unwrap();
+        assert_eq!(items, [Item(user("7"))]);
+
+        // Other formats have no objects for the registry to decode.
+        assert!(serde_json::from_str::<Item>(r#"{ "login": "c" }"#).is_err());
+    }
+
+    #[test]
+    fn test_options() {
+        let registry = registry();
+        let input = "bless( { login => 7 }, 'Bugzilla::User' )";
+        assert!(registry.from_str(input).is_err());
+        let mut deserializer = Deserializer::from_str(input).coerce(true);
+        assert_eq!(
+            registry.deserialize(&mut deserializer).unwrap(),
+            Object::User(User {
+                login: "7".to_owned()
+            })
+        );
+    }
+
+    #[test]
+    fn test_any() {
+        let mut registry = ClassRegistry::new();
+        registry
+            .register_any::<User>("Bugzilla::User")
+            .register_any::<Vec<u32>>("Bugzilla::Flags");
+
+        let user = registry
+            .from_str("bless( { login => 'c' }, 'Bugzilla::User' )")
+            .unwrap();
+        assert_eq!(
+            user.downcast_ref::<User>(),
+            Some(&User {
+                login: "c".to_owned()
+            })
+        );
+        let flags = registry
+            .from_str("bless( [1, 2], 'Bugzilla::Flags' )")
+            .unwrap();
+        assert_eq!(flags.downcast_ref::<Vec<u32>>(), Some(&vec![1, 2]));
+
+        let err = registry
+            .from_str("bless( {}, 'Bugzilla::Group' )")
+            .unwrap_err();
+        assert!(err.to_string().contains("`Bugzilla::Group`"), "{err}");
+        assert!(registry.from_str("{ login => 'c' }").is_err());
+        assert!(registry.is_registered("Bugzilla::User"));
+    }
+}
@@ -4,13 +4,80 @@ use key::KeySerializer;
 use serde::{ser, Serialize};
 
 use crate::{
+    blessed,
     error::{Error, Result},
-    quote::{float_quote, int_quote, single_quote},
+    perl_ref,
+    quote::{bytes_quote, float_quote, int_quote, single_quote},
 };
 
 pub struct Serializer {
     // This string starts empty and JSON is appended as values are serialized.
     output: String,
+    omit_none_fields: bool,
+    // Where the last `None` was written, if it hasn't turned out to be inside a `Some`.
+    none_at: Option<usize>,
+    // Whether each struct being written is a `Blessed`, innermost last.
+    structs: Vec<bool>,
+}
+
+impl Serializer {
+    pub fn new() -> Self {
+        Serializer {
+            output: String::new(),
+            omit_none_fields: false,
+            none_at: None,
+            structs: Vec::new(),
+        }
+    }
+
+    /// Leave out struct fields that are `None`, rather than writing them as `undef`, so that
+    /// they don't `exists` in Perl either.
+    pub fn omit_none_fields(mut self, omit: bool) -> Self {
+        self.omit_none_fields = omit;
+        self
+    }
+
+    /// The Perl written so far.
+    pub fn into_string(self) -> String {
+        self.output
+    }
+
+    // Struct fields and struct variant fields are written the same way.
+    fn serialize_struct_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        // A `Blessed` writes its value and then its class.
+        if self.structs.last() == Some(&true) {
+            if key != blessed::FIELDS[0] {
+                self.output += ",";
+            }
+            return value.serialize(&mut *self);
+        }
+
+        let start = self.output.len();
+        if !self.output.ends_with('{') {
+            self.output += ",";
+        }
+        let mut keyser = KeySerializer::default();
+        key.serialize(&mut keyser)?;
+        self.output += &keyser.output;
+        self.output += "=>";
+
+        let value_start = self.output.len();
+        self.none_at = None;
+        value.serialize(&mut *self)?;
+        if self.omit_none_fields && self.none_at == Some(value_start) {
+            self.output.truncate(start);
+        }
+        Ok(())
+    }
+}
+
+impl Default for Serializer {
+    fn default() -> Self {
+        Serializer::new()
+    }
 }
 
 // By convention, the public API of a Serde serializer is one or more `to_abc`
@@ -22,14 +89,12 @@ pub fn to_string<T>(value: &T) -> Result<String>
 where
     T: Serialize,
 {
-    let mut serializer = Serializer {
-        output: String::new(),
-    };
+    let mut serializer = Serializer::new();
     value.serialize(&mut serializer)?;
-    Ok(serializer.output)
+    Ok(serializer.into_string())
 }
 
-impl<'a> ser::Serializer for &'a mut Serializer {
+impl ser::Serializer for &mut Serializer {
     // The output type produced by this `Serializer` during successful
     // serialization. Most serializers that produce text or binary output should
     // set `Ok = ()` and serialize into an `io::Write` or buffer contained
@@ -56,8 +121,11 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     // Here we go with the simple methods. The following 12 methods receive one
     // of the primitive types of the data model and map it to JSON by appending
     // into the output string.
+
+    /// Perl has no boolean literals, so booleans are written as the `1` and `0` that read back
+    /// as them, even with strict booleans.
     fn serialize_bool(self, v: bool) -> Result<()> {
-        self.output += if v { "true" } else { "false" };
+        self.output += if v { "1" } else { "0" };
         Ok(())
     }
 
@@ -121,20 +189,15 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         Ok(())
     }
 
-    // Serialize a byte array as an array of bytes. Could also use a base64
-    // string here. Binary formats will typically represent byte arrays more
-    // compactly.
+    // Perl strings are byte strings, so bytes are written as one with escapes.
     fn serialize_bytes(self, v: &[u8]) -> Result<()> {
-        use serde::ser::SerializeSeq;
-        let mut seq = self.serialize_seq(Some(v.len()))?;
-        for byte in v {
-            seq.serialize_element(byte)?;
-        }
-        seq.end()
+        bytes_quote(&mut self.output, v);
+        Ok(())
     }
 
     // An absent optional is represented as the JSON `null`.
     fn serialize_none(self) -> Result<()> {
+        self.none_at = Some(self.output.len());
         self.serialize_unit()
     }
 
@@ -147,7 +210,13 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        value.serialize(self)
+        let start = self.output.len();
+        value.serialize(&mut *self)?;
+        // `Some(None)` is there, even if it is `undef`.
+        if self.none_at == Some(start) {
+            self.none_at = None;
+        }
+        Ok(())
     }
 
     // In Serde, unit means an anonymous value containing no data. Map this to
@@ -179,10 +248,13 @@ impl<'a> ser::Serializer for &'a mut Serializer {
 
     // As is done here, serializers are encouraged to treat newtype structs as
     // insignificant wrappers around the data they contain.
-    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<()>
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<()>
     where
         T: ?Sized + Serialize,
     {
+        if name == perl_ref::NAME {
+            self.output += "\\";
+        }
         value.serialize(self)
     }
 
@@ -271,8 +343,15 @@ impl<'a> ser::Serializer for &'a mut Serializer {
     // omit the field names when serializing structs because the corresponding
     // Deserialize implementation is required to know what the keys are without
     // looking at the serialized data.
-    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
-        self.serialize_map(Some(len))
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        let is_blessed = name == blessed::NAME;
+        self.structs.push(is_blessed);
+        if is_blessed {
+            self.output += "bless(";
+            Ok(self)
+        } else {
+            self.serialize_map(Some(len))
+        }
     }
 
     // Struct variants are represented in JSON as `{ NAME: { K: V, ... } }`.
@@ -289,6 +368,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
         variant.serialize(&mut keyser)?;
         self.output += &keyser.output;
         self.output += "=>{";
+        self.structs.push(false);
         Ok(self)
     }
 }
@@ -300,7 +380,7 @@ impl<'a> ser::Serializer for &'a mut Serializer {
 //
 // This impl is SerializeSeq so these methods are called after `serialize_seq`
 // is called on the Serializer.
-impl<'a> ser::SerializeSeq for &'a mut Serializer {
+impl ser::SerializeSeq for &mut Serializer {
     // Must match the `Ok` type of the serializer.
     type Ok = ();
     // Must match the `Error` type of the serializer.
@@ -325,7 +405,7 @@ impl<'a> ser::SerializeSeq for &'a mut Serializer {
 }
 
 // Same thing but for tuples.
-impl<'a> ser::SerializeTuple for &'a mut Serializer {
+impl ser::SerializeTuple for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -346,7 +426,7 @@ impl<'a> ser::SerializeTuple for &'a mut Serializer {
 }
 
 // Same thing but for tuple structs.
-impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
+impl ser::SerializeTupleStruct for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -375,7 +455,7 @@ impl<'a> ser::SerializeTupleStruct for &'a mut Serializer {
 //
 // So the `end` method in this impl is responsible for closing both the `]` and
 // the `}`.
-impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
+impl ser::SerializeTupleVariant for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -403,7 +483,7 @@ impl<'a> ser::SerializeTupleVariant for &'a mut Serializer {
 // `serialize_entry` method allows serializers to optimize for the case where
 // key and value are both available simultaneously. In JSON it doesn't make a
 // difference so the default behavior for `serialize_entry` is fine.
-impl<'a> ser::SerializeMap for &'a mut Serializer {
+impl ser::SerializeMap for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -447,7 +527,7 @@ impl<'a> ser::SerializeMap for &'a mut Serializer {
 
 // Structs are like maps in which the keys are constrained to be compile-time
 // constant strings.
-impl<'a> ser::SerializeStruct for &'a mut Serializer {
+impl ser::SerializeStruct for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -455,25 +535,22 @@ impl<'a> ser::SerializeStruct for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        if !self.output.ends_with('{') {
-            self.output += ",";
-        }
-        let mut keyser = KeySerializer::default();
-        key.serialize(&mut keyser)?;
-        self.output += &keyser.output;
-        self.output += "=>";
-        value.serialize(&mut **self)
+        self.serialize_struct_field(key, value)
     }
 
     fn end(self) -> Result<()> {
-        self.output += "}";
+        self.output += if self.structs.pop() == Some(true) {
+            ")"
+        } else {
+            "}"
+        };
         Ok(())
     }
 }
 
 // Similar to `SerializeTupleVariant`, here the `end` method is responsible for
 // closing both of the curly braces opened by `serialize_struct_variant`.
-impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
+impl ser::SerializeStructVariant for &mut Serializer {
     type Ok = ();
     type Error = Error;
 
@@ -481,17 +558,11 @@ impl<'a> ser::SerializeStructVariant for &'a mut Serializer {
     where
         T: ?Sized + Serialize,
     {
-        if !self.output.ends_with('{') {
-            self.output += ",";
-        }
-        let mut keyser = KeySerializer::default();
-        key.serialize(&mut keyser)?;
-        self.output += &keyser.output;
-        self.output += "=>";
-        value.serialize(&mut **self)
+        self.serialize_struct_field(key, value)
     }
 
     fn end(self) -> Result<()> {
+        self.structs.pop();
         self.output += "}}";
         Ok(())
     }
@@ -546,3 +617,69 @@ fn test_enum() {
     let expected = r#"{Struct=>{a=>1}}"#;
     assert_eq!(to_string(&s).unwrap(), expected);
 }
+
+#[test]
+fn test_bytes() {
+    let bytes = serde_bytes::Bytes::new(b"\x00a\"b\xff");
+    assert_eq!(to_string(&bytes).unwrap(), r#""\x00a\"b\xff""#);
+
+    let mut map = std::collections::BTreeMap::new();
+    map.insert(serde_bytes::ByteBuf::from(b"k\xe9y".to_vec()), 1);
+    assert_eq!(to_string(&map).unwrap(), r#"{"k\xe9y"=>1}"#);
+}
+
+#[test]
+fn test_omit_none_fields() {
+    #[derive(Serialize)]
+    struct Test {
+        a: Option<u32>,
+        b: Option<u32>,
+        c: Option<Option<u32>>,
+        d: Vec<Option<u32>>,
+        e: (),
+    }
+
+    let test = Test {
+        a: None,
+        b: Some(1),
+        c: Some(None),
+        d: vec![None],
+        e: (),
+    };
+    assert_eq!(
+        to_string(&test).unwrap(),
+        "{a=>undef,b=>1,c=>undef,d=>[undef],e=>undef}"
+    );
+
+    let mut serializer = Serializer::new().omit_none_fields(true);
+    test.serialize(&mut serializer).unwrap();
+    assert_eq!(
+        serializer.into_string(),
+        "{b=>1,c=>undef,d=>[undef],e=>undef}"
+    );
+
+    let mut serializer = Serializer::new().omit_none_fields(true);
+    Test { b: None, ..test }.serialize(&mut serializer).unwrap();
+    assert_eq!(serializer.into_string(), "{c=>undef,d=>[undef],e=>undef}");
+}
+
+#[test]
+fn test_blessed() {
+    let bug = crate::Blessed::new("Bugzilla::Bug", vec![crate::Blessed::new("A", 1)]);
+    assert_eq!(
+        to_string(&bug).unwrap(),
+        "bless([bless(1,'A')],'Bugzilla::Bug')"
+    );
+
+    #[derive(Serialize)]
+    struct Test {
+        a: crate::Blessed<std::collections::BTreeMap<&'static str, u32>>,
+        b: u32,
+    }
+
+    let test = Test {
+        a: crate::Blessed::new("X", [("k", 1)].into()),
+        b: 2,
+    };
+    assert_eq!(to_string(&test).unwrap(), "{a=>bless({k=>1},'X'),b=>2}");
+}
@@ -2,7 +2,7 @@ use serde::ser::{self, Serialize, Serializer};
 
 use crate::{
     error::{Error, Result},
-    quote::{bare_quote, float_quote, int_quote},
+    quote::{bare_quote, bytes_quote, float_quote, int_quote},
 };
 
 // A Perl serializer needs to validate that map keys are strings.
@@ -15,7 +15,7 @@ pub struct KeySerializer {
     pub(super) output: String,
 }
 
-impl<'a> Serializer for &'a mut KeySerializer {
+impl Serializer for &mut KeySerializer {
     type Ok = ();
     type Error = Error;
 
@@ -88,12 +88,11 @@ impl<'a> Serializer for &'a mut KeySerializer {
     }
 
     fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
-        float_quote(&mut self.output, v);
+        float_quote(&mut self.output, f64::from(v));
         Ok(())
     }
 
     fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
-        self.output += &v.to_string();
         float_quote(&mut self.output, v);
         Ok(())
     }
@@ -104,7 +103,7 @@ impl<'a> Serializer for &'a mut KeySerializer {
     }
 
     fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
-        self.output += &String::from_utf8_lossy(v);
+        bytes_quote(&mut self.output, v);
         Ok(())
     }
 
@@ -213,7 +212,7 @@ impl<'a> Serializer for &'a mut KeySerializer {
 //
 // This impl is SerializeSeq so these methods are called after `serialize_seq`
 // is called on the Serializer.
-impl<'a> ser::SerializeSeq for &'a mut KeySerializer {
+impl ser::SerializeSeq for &mut KeySerializer {
     // Must match the `Ok` type of the serializer.
     type Ok = ();
     // Must match the `Error` type of the serializer.
@@ -238,7 +237,7 @@ impl<'a> ser::SerializeSeq for &'a mut KeySerializer {
 }
 
 // Same thing but for tuples.
-impl<'a> ser::SerializeTuple for &'a mut KeySerializer {
+impl ser::SerializeTuple for &mut KeySerializer {
     type Ok = ();
     type Error = Error;
 
@@ -259,7 +258,7 @@ impl<'a> ser::SerializeTuple for &'a mut KeySerializer {
 }
 
 // Same thing but for tuple structs.
-impl<'a> ser::SerializeTupleStruct for &'a mut KeySerializer {
+impl ser::SerializeTupleStruct for &mut KeySerializer {
     type Ok = ();
     type Error = Error;
 
@@ -288,7 +287,7 @@ impl<'a> ser::SerializeTupleStruct for &'a mut KeySerializer {
 //
 // So the `end` method in this impl is responsible for closing both the `]` and
 // the `}`.
-impl<'a> ser::SerializeTupleVariant for &'a mut KeySerializer {
+impl ser::SerializeTupleVariant for &mut KeySerializer {
     type Ok = ();
     type Error = Error;
 
@@ -316,7 +315,7 @@ impl<'a> ser::SerializeTupleVariant for &'a mut KeySerializer {
 // `serialize_entry` method allows serializers to optimize for the case where
 // key and value are both available simultaneously. In JSON it doesn't make a
 // difference so the default behavior for `serialize_entry` is fine.
-impl<'a> ser::SerializeMap for &'a mut KeySerializer {
+impl ser::SerializeMap for &mut KeySerializer {
     type Ok = ();
     type Error = Error;
 
@@ -357,7 +356,7 @@ impl<'a> ser::SerializeMap for &'a mut KeySerializer {
 
 // Structs are like maps in which the keys are constrained to be compile-time
 // constant strings.
-impl<'a> ser::SerializeStruct for &'a mut KeySerializer {
+impl ser::SerializeStruct for &mut KeySerializer {
     type Ok = ();
     type Error = Error;
 
@@ -381,7 +380,7 @@ impl<'a> ser::SerializeStruct for &'a mut KeySerializer {
 
 // Similar to `SerializeTupleVariant`, here the `end` method is responsible for
 // closing both of the curly braces opened by `serialize_struct_variant`.
-impl<'a> ser::SerializeStructVariant for &'a mut KeySerializer {
+impl ser::SerializeStructVariant for &mut KeySerializer {
     type Ok = ();
     type Error = Error;
 
@@ -0,0 +1,666 @@
+//! The tree of Perl values that [`parse`](crate::parse) builds, and how to build, read and change
+//! one by hand.
+//!
+//! A [`Scalar`] is anything Perl can hold in a variable. Hashes and arrays are only ever reached
+//! through a reference, so [`Scalar::as_hash`] and [`Scalar::as_array`] look through it, and
+//! through the blessing of an object, the way `%$ref` and `@$ref` do in Perl.
+//!
+//! Indexing a scalar with a key or position gives `undef` for anything that is not there rather
+//! than panicking, like reading a missing element in Perl does. Indexing one mutably
+//! autovivifies a hash where there was `undef`, and panics where there is anything else that is
+//! not a hash or array.
+//!
+//! [`Scalar::select`] and [`Scalar::lookup`] pull values out of deeper in a tree by a [`Path`],
+//! written the way Perl subscripts (`->{products}[0]{name}`) or like a JSON Pointer.
+
+use std::{
+    borrow::Cow,
+    collections::{hash_map, HashMap},
+    ops,
+};
+
+use serde::Deserialize;
+
+use crate::de::ScalarDeserializer;
+use crate::error::Result;
+use crate::parser::{array_ref, hash_ref};
+pub use crate::parser::{
+    bytes_to_text, Array, Hash, Key, Object, Reference, Scalar, BOOLEAN_CLASSES,
+};
+
+pub(crate) mod de;
+mod path;
+mod ser;
+
+pub use self::path::{Match, Path, Segment};
+pub use self::ser::to_value;
+
+/// Deserializes a `T` from a tree, the way [`from_perl`](crate::from_perl) does from a borrowed
+/// one.
+pub fn from_value<'de, T>(value: Scalar<'de>) -> Result<T>
+where
+    T: Deserialize<'de>,
+{
+    T::deserialize(ScalarDeserializer::new(Cow::Owned(value)))
+}
+
+static UNDEF: Scalar<'static> = Scalar::Undefined;
+
+impl<'a> Scalar<'a> {
+    /// A reference to an array of `items`, written `[ ... ]`.
+    pub fn array<I>(items: I) -> Self
+    where
+        I: IntoIterator,
+        I::Item: Into<Scalar<'a>>,
+    {
+        array_ref(items.into_iter().map(Into::into).collect())
+    }
+
+    /// A reference to a hash of `entries`, written `{ ... }`.
+    pub fn hash<I, K, V>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<Key<'a>>,
+        V: Into<Scalar<'a>>,
+    {
+        hash_ref(
+            entries
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into()))
+                .collect(),
+        )
+    }
+
+    /// A reference to `value`, written `\VALUE`.
+    pub fn scalar_ref(value: impl Into<Scalar<'a>>) -> Self {
+        Scalar::Reference(Box::new(Reference::Scalar(value.into())))
+    }
+
+    /// `value`, which should be a reference, blessed into `class`.
+    pub fn object(class: impl Into<Cow<'a, str>>, value: impl Into<Scalar<'a>>) -> Self {
+        Scalar::Object(Box::new(Object {
+            class: class.into(),
+            value: value.into(),
+        }))
+    }
+
+    pub fn is_undef(&self) -> bool {
+        matches!(self, Scalar::Undefined)
+    }
+
+    /// Whether this is a reference of any kind, blessed or not.
+    pub fn is_ref(&self) -> bool {
+        matches!(self, Scalar::Reference(_) | Scalar::Object(_))
+    }
+
+    pub fn is_hash(&self) -> bool {
+        self.as_hash().is_some()
+    }
+
+    pub fn is_array(&self) -> bool {
+        self.as_array().is_some()
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, Scalar::Object(_))
+    }
+
+    pub fn is_number(&self) -> bool {
+        matches!(self, Scalar::Int(_) | Scalar::Float(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, Scalar::String(_) | Scalar::Bytes(_))
+    }
+
+    /// The string this holds, if it is one. Strings of bytes only count if they are UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Scalar::String(s) => Some(s),
+            Scalar::Bytes(b) => std::str::from_utf8(b).ok(),
+            _ => None,
+        }
+    }
+
+    /// The bytes of the string this holds, if it is one.
+    pub fn as_bytes(&self) -> Option<&[u8]> {
+        match self {
+            Scalar::String(s) => Some(s.as_bytes()),
+            Scalar::Bytes(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    /// The integer this holds, if it is one. Strings and floats are not numified.
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Scalar::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The number this holds as a float, if it is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Scalar::Int(i) => Some(*i as f64),
+            Scalar::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// The boolean this holds, if it is a JSON boolean object. Use [`Scalar::is_true`] to ask
+    /// what Perl makes of any scalar.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Scalar::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// The hash this refers to, if it is a hash reference, blessed or not.
+    pub fn as_hash(&self) -> Option<&Hash<'a>> {
+        match self.referent()? {
+            Reference::Hash(hash) => Some(hash),
+            _ => None,
+        }
+    }
+
+    pub fn as_hash_mut(&mut self) -> Option<&mut Hash<'a>> {
+        match self.referent_mut()? {
+            Reference::Hash(hash) => Some(hash),
+            _ => None,
+        }
+    }
+
+    /// The array this refers to, if it is an array reference, blessed or not.
+    pub fn as_array(&self) -> Option<&Array<'a>> {
+        match self.referent()? {
+            Reference::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Array<'a>> {
+        match self.referent_mut()? {
+            Reference::Array(array) => Some(array),
+            _ => None,
+        }
+    }
+
+    /// What this refers to, if it is a reference, blessed or not.
+    pub fn referent(&self) -> Option<&Reference<'a>> {
+        match self {
+            Scalar::Reference(reference) => Some(reference),
+            Scalar::Object(object) => object.value.referent(),
+            _ => None,
+        }
+    }
+
+    pub fn referent_mut(&mut self) -> Option<&mut Reference<'a>> {
+        match self {
+            Scalar::Reference(reference) => Some(reference),
+            Scalar::Object(object) => object.value.referent_mut(),
+            _ => None,
+        }
+    }
+
+    /// The class this was blessed into, if it is an object.
+    pub fn class(&self) -> Option<&str> {
+        match self {
+            Scalar::Object(object) => Some(&object.class),
+            _ => None,
+        }
+    }
+
+    /// The value at `index`, a hash key or array position, if there is one.
+    pub fn get<I: ScalarIndex>(&self, index: I) -> Option<&Scalar<'a>> {
+        index.index_into(self)
+    }
+
+    pub fn get_mut<I: ScalarIndex>(&mut self, index: I) -> Option<&mut Scalar<'a>> {
+        index.index_into_mut(self)
+    }
+
+    /// The entry for `key` in the hash this refers to, turning `undef` into a reference to an
+    /// empty hash first, or `None` if this is something other than a hash reference.
+    pub fn entry(
+        &mut self,
+        key: impl Into<Key<'a>>,
+    ) -> Option<hash_map::Entry<'_, Key<'a>, Scalar<'a>>> {
+        if self.is_undef() {
+            *self = hash_ref(HashMap::new());
+        }
+        Some(self.as_hash_mut()?.0.entry(key.into()))
+    }
+
+    /// Replace this with `undef`, returning what it was.
+    pub fn take(&mut self) -> Scalar<'a> {
+        std::mem::take(self)
+    }
+}
+
+/// A hash key or an array position that can index into a [`Scalar`].
+pub trait ScalarIndex: private::Sealed {
+    #[doc(hidden)]
+    fn index_into<'s, 'a>(&self, scalar: &'s Scalar<'a>) -> Option<&'s Scalar<'a>>;
+
+    #[doc(hidden)]
+    fn index_into_mut<'s, 'a>(&self, scalar: &'s mut Scalar<'a>) -> Option<&'s mut Scalar<'a>>;
+
+    /// Find or make room for the value at this index, for `IndexMut`.
+    #[doc(hidden)]
+    fn index_or_insert<'s, 'a>(&self, scalar: &'s mut Scalar<'a>) -> &'s mut Scalar<'a>;
+}
+
+impl ScalarIndex for usize {
+    fn index_into<'s, 'a>(&self, scalar: &'s Scalar<'a>) -> Option<&'s Scalar<'a>> {
+        scalar.as_array()?.0.get(*self)
+    }
+
+    fn index_into_mut<'s, 'a>(&self, scalar: &'s mut Scalar<'a>) -> Option<&'s mut Scalar<'a>> {
+        scalar.as_array_mut()?.0.get_mut(*self)
+    }
+
+    fn index_or_insert<'s, 'a>(&self, scalar: &'s mut Scalar<'a>) -> &'s mut Scalar<'a> {
+        let array = match scalar.as_array_mut() {
+            Some(array) => array,
+            None => panic!("cannot index into a scalar that is not an array with {self}"),
+        };
+        let len = array.0.len();
+        match array.0.get_mut(*self) {
+            Some(value) => value,
+            None => panic!("index {self} is out of bounds for an array of length {len}"),
+        }
+    }
+}
+
+impl ScalarIndex for str {
+    fn index_into<'s, 'a>(&self, scalar: &'s Scalar<'a>) -> Option<&'s Scalar<'a>> {
+        scalar.as_hash()?.0.get(self)
+    }
+
+    fn index_into_mut<'s, 'a>(&self, scalar: &'s mut Scalar<'a>) -> Option<&'s mut Scalar<'a>> {
+        scalar.as_hash_mut()?.0.get_mut(self)
+    }
+
+    fn index_or_insert<'s, 'a>(&self, scalar: &'s mut Scalar<'a>) -> &'s mut Scalar<'a> {
+        match scalar.entry(Key::from(self.to_owned())) {
+            Some(entry) => entry.or_default(),
+            None => panic!("cannot index into a scalar that is not a hash with {self:?}"),
+        }
+    }
+}
+
+impl ScalarIndex for String {
+    fn index_into<'s, 'a>(&self, scalar: &'s Scalar<'a>) -> Option<&'s Scalar<'a>> {
+        self.as_str().index_into(scalar)
+    }
+
+    fn index_into_mut<'s, 'a>(&self, scalar: &'s mut Scalar<'a>) -> Option<&'s mut Scalar<'a>> {
+        self.as_str().index_into_mut(scalar)
+    }
+
+    fn index_or_insert<'s, 'a>(&self, scalar: &'s mut Scalar<'a>) -> &'s mut Scalar<'a> {
+        self.as_str().index_or_insert(scalar)
+    }
+}
+
+impl<T> ScalarIndex for &T
+where
+    T: ?Sized + ScalarIndex,
+{
+    fn index_into<'s, 'a>(&self, scalar: &'s Scalar<'a>) -> Option<&'s Scalar<'a>> {
+        (**self).index_into(scalar)
+    }
+
+    fn index_into_mut<'s, 'a>(&self, scalar: &'s mut Scalar<'a>) -> Option<&'s mut Scalar<'a>> {
+        (**self).index_into_mut(scalar)
+    }
+
+    fn index_or_insert<'s, 'a>(&self, scalar: &'s mut Scalar<'a>) -> &'s mut Scalar<'a> {
+        (**self).index_or_insert(scalar)
+    }
+}
+
+mod private {
+    pub trait Sealed {}
+    impl Sealed for usize {}
+    impl Sealed for str {}
+    impl Sealed for String {}
+    impl<T: ?Sized + Sealed> Sealed for &T {}
+}
+
+impl<'a, I> ops::Index<I> for Scalar<'a>
+where
+    I: ScalarIndex,
+{
+    type Output = Scalar<'a>;
+
+    fn index(&self, index: I) -> &Scalar<'a> {
+        index.index_into(self).unwrap_or(&UNDEF)
+    }
+}
+
+impl<I> ops::IndexMut<I> for Scalar<'_>
+where
+    I: ScalarIndex,
+{
+    fn index_mut(&mut self, index: I) -> &mut Self {
+        index.index_or_insert(self)
+    }
+}
+
+impl<'a> ops::Deref for Hash<'a> {
+    type Target = HashMap<Key<'a>, Scalar<'a>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl ops::DerefMut for Hash<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<'a> ops::Deref for Array<'a> {
+    type Target = Vec<Scalar<'a>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl ops::DerefMut for Array<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+impl<'a> IntoIterator for Hash<'a> {
+    type Item = (Key<'a>, Scalar<'a>);
+    type IntoIter = hash_map::IntoIter<Key<'a>, Scalar<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'h, 'a> IntoIterator for &'h Hash<'a> {
+    type Item = (&'h Key<'a>, &'h Scalar<'a>);
+    type IntoIter = hash_map::Iter<'h, Key<'a>, Scalar<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'h, 'a> IntoIterator for &'h mut Hash<'a> {
+    type Item = (&'h Key<'a>, &'h mut Scalar<'a>);
+    type IntoIter = hash_map::IterMut<'h, Key<'a>, Scalar<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
+    }
+}
+
+impl<'a> IntoIterator for Array<'a> {
+    type Item = Scalar<'a>;
+    type IntoIter = std::vec::IntoIter<Scalar<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl<'r, 'a> IntoIterator for &'r Array<'a> {
+    type Item = &'r Scalar<'a>;
+    type IntoIter = std::slice::Iter<'r, Scalar<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl<'r, 'a> IntoIterator for &'r mut Array<'a> {
+    type Item = &'r mut Scalar<'a>;
+    type IntoIter = std::slice::IterMut<'r, Scalar<'a>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter_mut()
+    }
+}
+
+macro_rules! from_integer {
+    ($($ty:ty)*) => {
+        $(
+            impl From<$ty> for Scalar<'_> {
+                fn from(i: $ty) -> Self {
+                    Scalar::Int(i.into())
+                }
+            }
+        )*
+    };
+}
+
+from_integer! { i8 i16 i32 i64 u8 u16 u32 }
+
+impl From<f32> for Scalar<'_> {
+    fn from(f: f32) -> Self {
+        Scalar::Float(f.into())
+    }
+}
+
+impl From<f64> for Scalar<'_> {
+    fn from(f: f64) -> Self {
+        Scalar::Float(f)
+    }
+}
+
+impl From<bool> for Scalar<'_> {
+    fn from(b: bool) -> Self {
+        Scalar::Bool(b)
+    }
+}
+
+impl<'a> From<&'a str> for Scalar<'a> {
+    fn from(s: &'a str) -> Self {
+        Scalar::String(Cow::Borrowed(s))
+    }
+}
+
+impl From<String> for Scalar<'_> {
+    fn from(s: String) -> Self {
+        Scalar::String(Cow::Owned(s))
+    }
+}
+
+impl<'a> From<Cow<'a, str>> for Scalar<'a> {
+    fn from(s: Cow<'a, str>) -> Self {
+        Scalar::String(s)
+    }
+}
+
+/// `()` is `undef`.
+impl From<()> for Scalar<'_> {
+    fn from(_: ()) -> Self {
+        Scalar::Undefined
+    }
+}
+
+/// `None` is `undef`.
+impl<'a, T> From<Option<T>> for Scalar<'a>
+where
+    T: Into<Scalar<'a>>,
+{
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Scalar::Undefined, Into::into)
+    }
+}
+
+/// A vector becomes a reference to an array.
+impl<'a, T> From<Vec<T>> for Scalar<'a>
+where
+    T: Into<Scalar<'a>>,
+{
+    fn from(items: Vec<T>) -> Self {
+        Scalar::array(items)
+    }
+}
+
+/// A map becomes a reference to a hash.
+impl<'a, K, V> From<HashMap<K, V>> for Scalar<'a>
+where
+    K: Into<Key<'a>>,
+    V: Into<Scalar<'a>>,
+{
+    fn from(entries: HashMap<K, V>) -> Self {
+        Scalar::hash(entries)
+    }
+}
+
+impl<'a> From<Hash<'a>> for Scalar<'a> {
+    fn from(hash: Hash<'a>) -> Self {
+        Scalar::Reference(Box::new(Reference::Hash(hash)))
+    }
+}
+
+impl<'a> From<Array<'a>> for Scalar<'a> {
+    fn from(array: Array<'a>) -> Self {
+        Scalar::Reference(Box::new(Reference::Array(array)))
+    }
+}
+
+/// Collects into a reference to an array.
+impl<'a, T> FromIterator<T> for Scalar<'a>
+where
+    T: Into<Scalar<'a>>,
+{
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Self {
+        Scalar::array(items)
+    }
+}
+
+/// Collects into a reference to a hash.
+impl<'a, K, V> FromIterator<(K, V)> for Scalar<'a>
+where
+    K: Into<Key<'a>>,
+    V: Into<Scalar<'a>>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(entries: I) -> Self {
+        Scalar::hash(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    #[test]
+    fn test_access() {
+        let scalar = parse(
+            "{ products => [ { name => 'Firefox', id => 1 }, bless( { name => 'Thunderbird' }, \
+             'Product' ) ], count => 2, ratio => 0.5, flag => undef, ref => \\'x' }",
+        )
+        .unwrap();
+        assert_eq!(scalar["products"][0]["name"].as_str(), Some("Firefox"));
+        assert_eq!(scalar["products"][0]["id"].as_i64(), Some(1));
+        assert_eq!(scalar["products"][1]["name"].as_str(), Some("Thunderbird"));
+        assert_eq!(scalar["products"][1].class(), Some("Product"));
+        assert_eq!(scalar["count"].as_f64(), Some(2.0));
+        assert_eq!(scalar["ratio"].as_f64(), Some(0.5));
+        assert!(scalar["flag"].is_undef());
+        assert!(scalar["missing"].is_undef());
+        assert!(scalar["products"][5]["name"].is_undef());
+        assert!(scalar["count"]["x"].is_undef());
+        assert!(scalar["ref"].is_ref() && !scalar["ref"].is_hash());
+        assert!(scalar["products"].is_array() && scalar["products"][1].is_object());
+        assert_eq!(scalar.get("count"), Some(&Scalar::Int(2)));
+        assert_eq!(scalar.get("count".to_owned()), Some(&Scalar::Int(2)));
+        assert_eq!(scalar.get("missing"), None);
+        assert_eq!(
+            scalar["products"].get(1).and_then(Scalar::class),
+            Some("Product")
+        );
+
+        let names: Vec<&str> = scalar["products"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|product| product["name"].as_str())
+            .collect();
+        assert_eq!(names, ["Firefox", "Thunderbird"]);
+        let mut keys: Vec<&str> = scalar.as_hash().unwrap().keys().map(|k| &**k).collect();
+        keys.sort_unstable();
+        assert_eq!(keys, ["count", "flag", "products", "ratio", "ref"]);
+    }
+
+    #[test]
+    fn test_build() {
+        let scalar = Scalar::hash([
+            ("name", Scalar::from("Firefox")),
+            ("versions", vec![1, 2].into()),
+            ("icon", Scalar::scalar_ref(None::<&str>)),
+            ("owner", Scalar::object("User", Scalar::hash([("id", 7)]))),
+        ]);
+        let expected = parse(
+            "{ name => 'Firefox', versions => [1, 2], icon => \\undef, \
+             owner => bless( { id => 7 }, 'User' ) }",
+        )
+        .unwrap();
+        assert_eq!(scalar, expected);
+
+        let array: Scalar = (1..=3).collect();
+        assert_eq!(array, parse("[1, 2, 3]").unwrap());
+        let hash: Scalar = [("a", true)].into_iter().collect();
+        assert_eq!(hash["a"].as_bool(), Some(true));
+        assert_eq!(
+            Scalar::from(HashMap::from([("b", 1.5)]))["b"],
+            Scalar::Float(1.5)
+        );
+    }
+
+    #[test]
+    fn test_mutate() {
+        let mut scalar = parse("{ products => [ { name => 'Firefox' } ] }").unwrap();
+        scalar["products"][0]["name"] = "Thunderbird".into();
+        scalar["products"][0]["id"] = 2.into();
+        let products = scalar.get_mut("products").unwrap();
+        products
+            .as_array_mut()
+            .unwrap()
+            .push(Scalar::hash([("name", "Bugzilla")]));
+        *scalar.entry("count").unwrap().or_insert(0.into()) = 2.into();
+        assert_eq!(
+            scalar,
+            parse(
+                "{ products => [ { name => 'Thunderbird', id => 2 }, { name => 'Bugzilla' } ], \
+                 count => 2 }"
+            )
+            .unwrap()
+        );
+
+        // Perl autovivifies hashes in `undef`, and so does indexing mutably.
+        let mut scalar = Scalar::Undefined;
+        scalar["a"]["b"] = 1.into();
+        assert_eq!(scalar, parse("{ a => { b => 1 } }").unwrap());
+        assert!(Scalar::from(1).entry("a").is_none());
+
+        let mut scalar = parse("bless( { a => 1 }, 'X' )").unwrap();
+        for (_, value) in scalar.as_hash_mut().unwrap() {
+            *value = (value.as_i64().unwrap() + 1).into();
+        }
+        assert_eq!(scalar["a"].as_i64(), Some(2));
+        assert_eq!(scalar["a"].take(), Scalar::Int(2));
+        assert!(scalar["a"].is_undef() && scalar.as_hash().unwrap().contains_key("a"));
+    }
+
+    #[test]
+    #[should_panic(expected = "not an array")]
+    fn test_index_mut_panics() {
+        let mut scalar = Scalar::from("x");
+        scalar[0] = 1.into();
+    }
+}
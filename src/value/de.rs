@@ -0,0 +1,237 @@
+use serde::de::{
+    self, Deserialize, DeserializeSeed, Deserializer, EnumAccess, MapAccess, SeqAccess,
+    VariantAccess, Visitor,
+};
+use std::{borrow::Cow, collections::HashMap, fmt};
+
+use crate::parser::{array_ref, hash_ref, Key, Scalar};
+use crate::{blessed, perl_ref, Blessed, PerlRef};
+
+/// The newtype struct name that tells this crate's deserializers to hand over a [`Scalar`] whole,
+/// scalar references and blessings included, which any other format sees as a plain newtype.
+pub(crate) const NAME: &str = "$serde_perl_dumper::private::Scalar";
+
+/// Builds a tree from any format, owning all of it so that formats that cannot lend out their
+/// strings can build one too.
+///
+/// This crate's own deserializers hand over scalar references and objects as they are, so a
+/// tree built from Perl source this way is the one [`parse`](crate::parse) builds.
+impl<'de> Deserialize<'de> for Scalar<'_> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_newtype_struct(NAME, ScalarVisitor)
+    }
+}
+
+struct ScalarVisitor;
+
+impl<'de> Visitor<'de> for ScalarVisitor {
+    type Value = Scalar<'static>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("any value")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(Scalar::Bool(v))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Scalar::Int(v))
+    }
+
+    /// Integers too large for Perl to hold are kept exactly in a string, which is how
+    /// Data::Dumper writes them.
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(i64::try_from(v).map_or_else(|_| Scalar::String(v.to_string().into()), Scalar::Int))
+    }
+
+    fn visit_i128<E: de::Error>(self, v: i128) -> Result<Self::Value, E> {
+        Ok(i64::try_from(v).map_or_else(|_| Scalar::String(v.to_string().into()), Scalar::Int))
+    }
+
+    fn visit_u128<E: de::Error>(self, v: u128) -> Result<Self::Value, E> {
+        Ok(i64::try_from(v).map_or_else(|_| Scalar::String(v.to_string().into()), Scalar::Int))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Scalar::Float(v))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Scalar::String(Cow::Owned(v.to_owned())))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(Scalar::String(Cow::Owned(v)))
+    }
+
+    fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        Ok(Scalar::Bytes(Cow::Owned(v.to_owned())))
+    }
+
+    fn visit_byte_buf<E: de::Error>(self, v: Vec<u8>) -> Result<Self::Value, E> {
+        Ok(Scalar::Bytes(Cow::Owned(v)))
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Scalar::Undefined)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(Scalar::Undefined)
+    }
+
+    fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Deserialize::deserialize(deserializer)
+    }
+
+    fn visit_newtype_struct<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(self)
+    }
+
+    /// This crate's deserializers hand over scalar references and objects as variants named for
+    /// [`PerlRef`] and [`Blessed`].
+    fn visit_enum<A>(self, data: A) -> Result<Self::Value, A::Error>
+    where
+        A: EnumAccess<'de>,
+    {
+        let (variant, value) = data.variant::<String>()?;
+        match &*variant {
+            perl_ref::NAME => {
+                let PerlRef(referent) = value.newtype_variant::<PerlRef<Scalar>>()?;
+                Ok(Scalar::scalar_ref(referent))
+            }
+            blessed::NAME => {
+                let object = value.newtype_variant::<Blessed<Scalar>>()?;
+                Ok(Scalar::object(object.class, object.value))
+            }
+            _ => Err(de::Error::invalid_value(
+                de::Unexpected::Str(&variant),
+                &"a scalar reference or an object",
+            )),
+        }
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut items = Vec::with_capacity(seq.size_hint().unwrap_or(0).min(4096));
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(array_ref(items))
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut hash = HashMap::with_capacity(map.size_hint().unwrap_or(0).min(4096));
+        while let Some((key, value)) = map.next_entry_seed(KeySeed, std::marker::PhantomData)? {
+            hash.insert(key, value);
+        }
+        Ok(hash_ref(hash))
+    }
+}
+
+/// Reads a hash key, stringifying numbers and booleans the way the serializer writes them.
+struct KeySeed;
+
+impl<'de> DeserializeSeed<'de> for KeySeed {
+    type Value = Key<'static>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(self)
+    }
+}
+
+impl Visitor<'_> for KeySeed {
+    type Value = Key<'static>;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a hash key")
+    }
+
+    fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+        Ok(Key::from(if v { "1" } else { "0" }.to_owned()))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+        Ok(Key::from(v.to_string()))
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+        Ok(Key::from(v.to_string()))
+    }
+
+    fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+        Ok(Key::from(v.to_string()))
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        Ok(Key::from(v.to_owned()))
+    }
+
+    fn visit_string<E: de::Error>(self, v: String) -> Result<Self::Value, E> {
+        Ok(Key::from(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_perl, from_str, from_value, parse, to_string};
+
+    #[test]
+    fn test_from_json() {
+        let json = r#"{ "name": "Firefox", "versions": [1, 2.5, null], "open": true,
+                        "big": 18446744073709551615, "owner": { "id": 7 } }"#;
+        let scalar: Scalar = serde_json::from_str(json).unwrap();
+        let mut expected = parse(
+            "{ name => 'Firefox', versions => [1, 2.5, undef], open => 1, \
+             big => '18446744073709551615', owner => { id => 7 } }",
+        )
+        .unwrap();
+        expected["open"] = Scalar::Bool(true);
+        assert_eq!(scalar, expected);
+
+        let scalar: Scalar = serde_json::from_value(serde_json::json!({ "1": [] })).unwrap();
+        assert_eq!(scalar, parse("{ 1 => [] }").unwrap());
+    }
+
+    #[test]
+    fn test_from_perl() {
+        let input = "{ a => [ 1, 'x', undef, { b => 1.5 } ], 'c d' => \"\\x{263A}\" }";
+        assert_eq!(from_str::<Scalar>(input).unwrap(), parse(input).unwrap());
+
+        // Scalar references and blessings are kept, and come back out of the serializer.
+        let input = "[ \\1, bless( { a => \\\\'x' }, 'X' ), \\bless( [], 'Y' ), \\undef ]";
+        let scalar: Scalar = from_str(input).unwrap();
+        assert_eq!(scalar, parse(input).unwrap());
+        assert_eq!(from_perl::<Scalar>(&scalar).unwrap(), scalar);
+        assert_eq!(from_value::<Scalar>(scalar.clone()).unwrap(), scalar);
+        let output = to_string(&scalar).unwrap();
+        assert_eq!(
+            output,
+            "[\\1,bless({a=>\\\\'x'},'X'),\\bless([],'Y'),\\undef]"
+        );
+        assert_eq!(from_str::<Scalar>(&output).unwrap(), scalar);
+
+        // As are big numbers, rather than the number they hold.
+        let input = "bless( { sign => '+', value => [5] }, 'Math::BigInt' )";
+        assert_eq!(from_str::<Scalar>(input).unwrap(), parse(input).unwrap());
+    }
+}
@@ -0,0 +1,561 @@
+use std::str::FromStr;
+
+use crate::error::{Error, Result};
+use crate::parser::Scalar;
+use crate::quote::{bare_quote, int_quote};
+
+/// A query that picks values out of a [`Scalar`], parsed from one of two forms.
+///
+/// The arrow form subscripts like Perl does: `->{products}[0]{name}`, with the arrows optional.
+/// Keys are barewords or quoted strings, and array positions may be negative to count from the
+/// end. `{*}` selects every value of a hash, `[*]` every element of an array, and `..` the
+/// value it is applied to and everything below it, so `..{name}` finds every `name` key at any
+/// depth.
+///
+/// The pointer form is like a JSON Pointer: `/products/0/name`, where each segment is a key of a
+/// hash or a position in an array and `~1` and `~0` stand for `/` and `~`. A `*` segment
+/// selects everything in a hash or array and a `**` segment works like `..`.
+///
+/// Both forms look through blessings, and an empty path selects the value itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Step {
+    Key(String),
+    Index(i64),
+    /// A pointer segment, which is a key of a hash or, if it is a number, a position in an array.
+    Member(String),
+    Values,
+    Elements,
+    Children,
+    Descendants,
+}
+
+/// One subscript on the way to a [`Match`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Segment<'s> {
+    Key(&'s str),
+    Index(usize),
+}
+
+/// A value selected by a [`Path`], with the subscripts that lead to it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match<'s, 'a> {
+    pub path: Vec<Segment<'s>>,
+    pub value: &'s Scalar<'a>,
+}
+
+impl Match<'_, '_> {
+    /// The path to the value in the arrow form, like `->{products}[0]{name}`.
+    pub fn arrow_path(&self) -> String {
+        let mut output = String::new();
+        for segment in &self.path {
+            if output.is_empty() {
+                output += "->";
+            }
+            match segment {
+                Segment::Key(key) => {
+                    output += "{";
+                    bare_quote(&mut output, key);
+                    output += "}";
+                }
+                Segment::Index(index) => {
+                    output += "[";
+                    int_quote(&mut output, *index);
+                    output += "]";
+                }
+            }
+        }
+        output
+    }
+
+    /// The path to the value in the pointer form, like `/products/0/name`.
+    pub fn pointer(&self) -> String {
+        let mut output = String::new();
+        for segment in &self.path {
+            output += "/";
+            match segment {
+                Segment::Key(key) => output += &key.replace('~', "~0").replace('/', "~1"),
+                Segment::Index(index) => int_quote(&mut output, *index),
+            }
+        }
+        output
+    }
+}
+
+impl Path {
+    /// Parses a path in the pointer form if it starts with `/`, and in the arrow form otherwise.
+    pub fn parse(path: &str) -> Result<Path> {
+        let steps = if path.starts_with('/') {
+            parse_pointer(path)
+        } else {
+            PathParser { path, pos: 0 }.parse()?
+        };
+        Ok(Path { steps })
+    }
+
+    /// Every value in `scalar` this path selects, in the order they appear with hash keys
+    /// sorted.
+    pub fn select<'s, 'a>(&self, scalar: &'s Scalar<'a>) -> Vec<Match<'s, 'a>> {
+        let mut trail = Trail::default();
+        let mut found = vec![Found {
+            at: None,
+            value: scalar,
+        }];
+        for step in &self.steps {
+            let mut next = Vec::new();
+            for found in found {
+                step.apply(found, &mut trail, &mut next);
+            }
+            found = next;
+        }
+        found
+            .into_iter()
+            .map(|found| Match {
+                path: trail.path(found.at),
+                value: found.value,
+            })
+            .collect()
+    }
+
+    /// The first value in `scalar` this path selects.
+    pub fn first<'s, 'a>(&self, scalar: &'s Scalar<'a>) -> Option<&'s Scalar<'a>> {
+        // Without a selector there is at most one match, so there is no need to collect them.
+        if !self.steps.iter().all(Step::is_subscript) {
+            return self.select(scalar).first().map(|found| found.value);
+        }
+        self.steps.iter().try_fold(scalar, |scalar, step| {
+            step.subscript(scalar).map(|(_, v)| v)
+        })
+    }
+}
+
+impl FromStr for Path {
+    type Err = Error;
+
+    fn from_str(path: &str) -> Result<Path> {
+        Path::parse(path)
+    }
+}
+
+impl Step {
+    fn is_subscript(&self) -> bool {
+        matches!(self, Step::Key(_) | Step::Index(_) | Step::Member(_))
+    }
+
+    /// The value a key or position selects, if there is one.
+    fn subscript<'s, 'a>(&self, scalar: &'s Scalar<'a>) -> Option<(Segment<'s>, &'s Scalar<'a>)> {
+        match self {
+            Step::Key(key) => key_of(scalar, key),
+            Step::Index(index) => {
+                let array = &scalar.as_array()?.0;
+                let index = if *index < 0 {
+                    array
+                        .len()
+                        .checked_sub(usize::try_from(index.unsigned_abs()).ok()?)?
+                } else {
+                    usize::try_from(*index).ok()?
+                };
+                Some((Segment::Index(index), array.get(index)?))
+            }
+            Step::Member(member) => match scalar.as_array() {
+                Some(array) if member.bytes().all(|b| b.is_ascii_digit()) => {
+                    let index = member.parse().ok()?;
+                    Some((Segment::Index(index), array.0.get(index)?))
+                }
+                _ => key_of(scalar, member),
+            },
+            _ => None,
+        }
+    }
+
+    fn apply<'s, 'a>(
+        &self,
+        found: Found<'s, 'a>,
+        trail: &mut Trail<'s>,
+        matches: &mut Vec<Found<'s, 'a>>,
+    ) {
+        let mut child = |(segment, value): (Segment<'s>, &'s Scalar<'a>)| Found {
+            at: Some(trail.push(found.at, segment)),
+            value,
+        };
+        match self {
+            Step::Values => matches.extend(values(found.value).into_iter().map(child)),
+            Step::Elements => matches.extend(elements(found.value).map(child)),
+            Step::Children => {
+                matches.extend(values(found.value).into_iter().map(&mut child));
+                matches.extend(elements(found.value).map(child));
+            }
+            Step::Descendants => descendants(found, trail, matches),
+            _ => matches.extend(self.subscript(found.value).map(child)),
+        }
+    }
+}
+
+/// A value selected so far, and where in the [`Trail`] its path ends.
+#[derive(Clone, Copy)]
+struct Found<'s, 'a> {
+    at: Option<usize>,
+    value: &'s Scalar<'a>,
+}
+
+/// The subscripts leading to the values selected so far, each linked to the one before it, so
+/// that values deep in a tree share the path to them rather than each holding a copy. Only the
+/// values a [`Path`] ends up selecting have their paths built.
+#[derive(Default)]
+struct Trail<'s> {
+    links: Vec<(Segment<'s>, Option<usize>)>,
+}
+
+impl<'s> Trail<'s> {
+    /// Adds `segment` after the path ending at `parent`, giving where the new path ends.
+    fn push(&mut self, parent: Option<usize>, segment: Segment<'s>) -> usize {
+        self.links.push((segment, parent));
+        self.links.len() - 1
+    }
+
+    fn path(&self, mut at: Option<usize>) -> Vec<Segment<'s>> {
+        let mut path = Vec::new();
+        while let Some(link) = at {
+            let (segment, parent) = self.links[link];
+            path.push(segment);
+            at = parent;
+        }
+        path.reverse();
+        path
+    }
+}
+
+fn key_of<'s, 'a>(scalar: &'s Scalar<'a>, key: &str) -> Option<(Segment<'s>, &'s Scalar<'a>)> {
+    let (key, value) = scalar.as_hash()?.0.get_key_value(key)?;
+    Some((Segment::Key(key), value))
+}
+
+/// The values of the hash `scalar` refers to, sorted by key.
+fn values<'s, 'a>(scalar: &'s Scalar<'a>) -> Vec<(Segment<'s>, &'s Scalar<'a>)> {
+    let mut values: Vec<_> = scalar
+        .as_hash()
+        .into_iter()
+        .flat_map(|hash| &hash.0)
+        .map(|(key, value)| (&**key, value))
+        .collect();
+    values.sort_unstable_by_key(|&(key, _)| key);
+    values
+        .into_iter()
+        .map(|(key, value)| (Segment::Key(key), value))
+        .collect()
+}
+
+fn elements<'s, 'a>(scalar: &'s Scalar<'a>) -> impl Iterator<Item = (Segment<'s>, &'s Scalar<'a>)> {
+    scalar
+        .as_array()
+        .into_iter()
+        .flat_map(|array| array.0.iter().enumerate())
+        .map(|(index, value)| (Segment::Index(index), value))
+}
+
+/// Adds `found` and everything below it, depth first, without recursing so that deeply nested
+/// values cannot overflow the stack.
+fn descendants<'s, 'a>(
+    found: Found<'s, 'a>,
+    trail: &mut Trail<'s>,
+    matches: &mut Vec<Found<'s, 'a>>,
+) {
+    let mut stack = vec![found];
+    while let Some(found) = stack.pop() {
+        let start = stack.len();
+        for (segment, value) in values(found.value).into_iter().chain(elements(found.value)) {
+            let at = Some(trail.push(found.at, segment));
+            stack.push(Found { at, value });
+        }
+        stack[start..].reverse();
+        matches.push(found);
+    }
+}
+
+fn parse_pointer(path: &str) -> Vec<Step> {
+    path[1..]
+        .split('/')
+        .map(|segment| match segment {
+            "*" => Step::Children,
+            "**" => Step::Descendants,
+            _ => Step::Member(segment.replace("~1", "/").replace("~0", "~")),
+        })
+        .collect()
+}
+
+struct PathParser<'p> {
+    path: &'p str,
+    pos: usize,
+}
+
+impl PathParser<'_> {
+    fn parse(mut self) -> Result<Vec<Step>> {
+        let mut steps = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.eat("->") {
+                self.skip_whitespace();
+                if !self.path[self.pos..].starts_with(['{', '[']) {
+                    return Err(self.error("expected `{` or `[` after `->`"));
+                }
+            }
+            let Some(c) = self.peek() else {
+                return Ok(steps);
+            };
+            self.pos += c.len_utf8();
+            let step = match c {
+                '{' => self.hash_subscript()?,
+                '[' => self.array_subscript()?,
+                '.' if self.eat(".") => Step::Descendants,
+                _ => {
+                    self.pos -= c.len_utf8();
+                    return Err(self.error(&format!("expected a subscript, found `{c}`")));
+                }
+            };
+            steps.push(step);
+        }
+    }
+
+    fn hash_subscript(&mut self) -> Result<Step> {
+        self.skip_whitespace();
+        let step = match self.peek() {
+            Some('*') => {
+                self.pos += 1;
+                Step::Values
+            }
+            Some(quote @ ('\'' | '"')) => {
+                self.pos += 1;
+                Step::Key(self.quoted(quote)?)
+            }
+            _ => {
+                let start = self.pos;
+                if self.peek() == Some('-') {
+                    self.pos += 1;
+                }
+                self.skip_while(|c| c.is_alphanumeric() || c == '_');
+                if self.pos == start {
+                    return Err(self.error("expected a hash key"));
+                }
+                Step::Key(self.path[start..self.pos].to_owned())
+            }
+        };
+        self.close('}')?;
+        Ok(step)
+    }
+
+    fn array_subscript(&mut self) -> Result<Step> {
+        self.skip_whitespace();
+        let step = if self.eat("*") {
+            Step::Elements
+        } else {
+            let start = self.pos;
+            if self.peek() == Some('-') {
+                self.pos += 1;
+            }
+            self.skip_while(|c| c.is_ascii_digit());
+            let index = self.path[start..self.pos].parse().map_err(|_| {
+                self.pos = start;
+                self.error("expected an array index")
+            })?;
+            Step::Index(index)
+        };
+        self.close(']')?;
+        Ok(step)
+    }
+
+    /// The rest of a string quoted with `quote`, where a backslash escapes the next character.
+    fn quoted(&mut self, quote: char) -> Result<String> {
+        let mut key = String::new();
+        let mut chars = self.path[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => key.push(escaped),
+                    None => break,
+                },
+                c if c == quote => {
+                    self.pos += i + 1;
+                    return Ok(key);
+                }
+                c => key.push(c),
+            }
+        }
+        self.pos = self.path.len();
+        Err(self.error("unterminated string"))
+    }
+
+    fn close(&mut self, bracket: char) -> Result<()> {
+        self.skip_whitespace();
+        if self.peek() == Some(bracket) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected `{bracket}`")))
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.path[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.path[self.pos..].starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn skip_while(&mut self, f: impl Fn(char) -> bool) {
+        let rest = &self.path[self.pos..];
+        self.pos += rest.find(|c| !f(c)).unwrap_or(rest.len());
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.skip_while(char::is_whitespace);
+    }
+
+    fn error(&self, message: &str) -> Error {
+        Error::Syntax {
+            message: message.to_owned(),
+            line: 1,
+            column: self.path[..self.pos].chars().count() + 1,
+        }
+    }
+}
+
+impl<'a> Scalar<'a> {
+    /// Every value `path` selects, with the path to each, as described for [`Path`].
+    pub fn select(&self, path: &str) -> Result<Vec<Match<'_, 'a>>> {
+        Ok(Path::parse(path)?.select(self))
+    }
+
+    /// The first value `path` selects, as described for [`Path`].
+    pub fn lookup(&self, path: &str) -> Result<Option<&Scalar<'a>>> {
+        Ok(Path::parse(path)?.first(self))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse;
+
+    fn catalog() -> Scalar<'static> {
+        perl!({
+            products => [
+                { name => "pen", price => 1.5, tags => ["office"] },
+                { name => "ink", price => 4, "sku/id" => "x~1" },
+            ],
+            owner => bless({ name => "shop" }, "Owner"),
+        })
+    }
+
+    fn paths(matches: &[Match]) -> Vec<String> {
+        matches.iter().map(Match::arrow_path).collect()
+    }
+
+    #[test]
+    fn test_lookup() {
+        let catalog = catalog();
+        let pen = Scalar::from("pen");
+        for path in [
+            "->{products}[0]{name}",
+            "{products}->[0] -> {name}",
+            "{ 'products' } [ -2 ] { \"name\" }",
+            "/products/0/name",
+        ] {
+            assert_eq!(catalog.lookup(path).unwrap(), Some(&pen), "{path}");
+        }
+        assert_eq!(
+            catalog.lookup("/products/1/sku~1id").unwrap(),
+            Some(&Scalar::from("x~1"))
+        );
+        assert_eq!(
+            catalog.lookup("->{owner}{name}").unwrap(),
+            Some(&Scalar::from("shop"))
+        );
+        assert_eq!(catalog.lookup("").unwrap(), Some(&catalog));
+        for path in [
+            "->{products}[2]",
+            "[0]",
+            "->{products}[-3]",
+            "/products/name",
+        ] {
+            assert_eq!(catalog.lookup(path).unwrap(), None, "{path}");
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let catalog = catalog();
+        let matches = catalog.select("->{products}[*]{name}").unwrap();
+        assert_eq!(
+            paths(&matches),
+            ["->{products}[0]{name}", "->{products}[1]{name}"]
+        );
+        assert_eq!(matches[1].value, &Scalar::from("ink"));
+        assert_eq!(matches[1].pointer(), "/products/1/name");
+
+        let matches = catalog.select("..{name}").unwrap();
+        assert_eq!(
+            paths(&matches),
+            [
+                "->{owner}{name}",
+                "->{products}[0]{name}",
+                "->{products}[1]{name}"
+            ]
+        );
+        assert_eq!(
+            paths(&catalog.select("/products/1/*").unwrap()),
+            [
+                "->{products}[1]{name}",
+                "->{products}[1]{price}",
+                "->{products}[1]{'sku/id'}"
+            ]
+        );
+        assert_eq!(catalog.select("/**").unwrap().len(), 13);
+        assert_eq!(catalog.select("{products}{*}").unwrap(), []);
+        assert_eq!(catalog.select("{products}[*]").unwrap().len(), 2);
+
+        let nested = parse(&format!("{}1{}", "[".repeat(2000), "]".repeat(2000))).unwrap();
+        let matches = nested.select("..").unwrap();
+        assert_eq!(matches.len(), 2001);
+        assert_eq!(matches[2000].path, [Segment::Index(0); 2000]);
+
+        // Only the paths of what is selected are built, so picking a little out of a deep tree
+        // takes no more than the tree does.
+        let depth = 20000;
+        let input = format!(
+            "{}{{ name => 'x' }}{}",
+            "{ a => ".repeat(depth),
+            " }".repeat(depth)
+        );
+        let deep = parse(&input).unwrap();
+        let matches = deep.select("..{name}").unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].path.len(), depth + 1);
+        assert_eq!(matches[0].path[depth], Segment::Key("name"));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        for (path, column) in [
+            ("->", 3),
+            ("{a", 3),
+            ("[x]", 2),
+            ("{'a}", 5),
+            ("a", 1),
+            ("{}", 2),
+        ] {
+            match Path::parse(path) {
+                Err(Error::Syntax { column: c, .. }) => assert_eq!(c, column, "{path}"),
+                result => panic!("{path}: {result:?}"),
+            }
+        }
+    }
+}
@@ -0,0 +1,651 @@
+use serde::ser::{self, Impossible, Serialize};
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::error::{Error, Result};
+use crate::parser::{array_ref, hash_ref, Key, Object, Scalar};
+use crate::quote::{float_quote, int_quote};
+use crate::{blessed, perl_ref};
+
+/// Builds the tree that [`to_string`](crate::to_string) would write `value` as, so that
+/// [`Blessed`](crate::Blessed) values become objects and [`PerlRef`](crate::PerlRef)s scalar
+/// references.
+pub fn to_value<T>(value: &T) -> Result<Scalar<'static>>
+where
+    T: ?Sized + Serialize,
+{
+    value.serialize(ValueSerializer)
+}
+
+struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Scalar<'static>;
+    type Error = Error;
+
+    type SerializeSeq = SerializeArray;
+    type SerializeTuple = SerializeArray;
+    type SerializeTupleStruct = SerializeArray;
+    type SerializeTupleVariant = SerializeArray;
+    type SerializeMap = SerializeHash;
+    type SerializeStruct = SerializeStruct;
+    type SerializeStructVariant = SerializeHash;
+
+    fn serialize_bool(self, v: bool) -> Result<Self::Ok> {
+        Ok(Scalar::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Self::Ok> {
+        Ok(Scalar::Int(v))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Self::Ok> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    // Too large for an `Int`, so kept exactly in a string like the text serializer writes it.
+    fn serialize_u64(self, v: u64) -> Result<Self::Ok> {
+        Ok(i64::try_from(v).map_or_else(|_| Scalar::String(v.to_string().into()), Scalar::Int))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Self::Ok> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Self::Ok> {
+        Ok(Scalar::Float(v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Self::Ok> {
+        Ok(Scalar::String(Cow::Owned(v.to_string())))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Self::Ok> {
+        Ok(Scalar::String(Cow::Owned(v.to_owned())))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok> {
+        Ok(Scalar::Bytes(Cow::Owned(v.to_owned())))
+    }
+
+    fn serialize_none(self) -> Result<Self::Ok> {
+        Ok(Scalar::Undefined)
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Self::Ok> {
+        Ok(Scalar::Undefined)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Self::Ok> {
+        self.serialize_unit()
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<Self::Ok> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T>(self, name: &'static str, value: &T) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        let value = value.serialize(self)?;
+        Ok(if name == perl_ref::NAME {
+            Scalar::scalar_ref(value)
+        } else {
+            value
+        })
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<Self::Ok>
+    where
+        T: ?Sized + Serialize,
+    {
+        Ok(tag(Some(variant), value.serialize(self)?))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Ok(SerializeArray {
+            items: Vec::with_capacity(len.unwrap_or(0)),
+            variant: None,
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Self::SerializeTuple> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Ok(SerializeArray {
+            items: Vec::with_capacity(len),
+            variant: Some(variant),
+        })
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> Result<Self::SerializeMap> {
+        Ok(SerializeHash {
+            hash: HashMap::with_capacity(len.unwrap_or(0)),
+            next_key: None,
+            variant: None,
+        })
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> Result<Self::SerializeStruct> {
+        if name == blessed::NAME {
+            Ok(SerializeStruct::Blessed {
+                value: Scalar::Undefined,
+                class: Cow::Borrowed(""),
+            })
+        } else {
+            self.serialize_map(Some(len)).map(SerializeStruct::Hash)
+        }
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Ok(SerializeHash {
+            hash: HashMap::with_capacity(len),
+            next_key: None,
+            variant: Some(variant),
+        })
+    }
+}
+
+/// Wraps a value built for an enum variant in the `{ VARIANT => VALUE }` hash that tags it.
+fn tag(variant: Option<&'static str>, value: Scalar<'static>) -> Scalar<'static> {
+    match variant {
+        Some(variant) => hash_ref(HashMap::from([(Key::Borrowed(variant), value)])),
+        None => value,
+    }
+}
+
+struct SerializeArray {
+    items: Vec<Scalar<'static>>,
+    variant: Option<&'static str>,
+}
+
+impl ser::SerializeSeq for SerializeArray {
+    type Ok = Scalar<'static>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.items.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(tag(self.variant, array_ref(self.items)))
+    }
+}
+
+impl ser::SerializeTuple for SerializeArray {
+    type Ok = Scalar<'static>;
+    type Error = Error;
+
+    fn serialize_element<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeArray {
+    type Ok = Scalar<'static>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleVariant for SerializeArray {
+    type Ok = Scalar<'static>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+struct SerializeHash {
+    hash: HashMap<Key<'static>, Scalar<'static>>,
+    next_key: Option<Key<'static>>,
+    variant: Option<&'static str>,
+}
+
+impl ser::SerializeMap for SerializeHash {
+    type Ok = Scalar<'static>;
+    type Error = Error;
+
+    fn serialize_key<T>(&mut self, key: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.next_key = Some(Key::from(key.serialize(KeySerializer)?));
+        Ok(())
+    }
+
+    fn serialize_value<T>(&mut self, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        let key = self
+            .next_key
+            .take()
+            .ok_or_else(|| <Error as ser::Error>::custom("value serialized before its key"))?;
+        self.hash.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        Ok(tag(self.variant, hash_ref(self.hash)))
+    }
+}
+
+impl ser::SerializeStructVariant for SerializeHash {
+    type Ok = Scalar<'static>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        self.hash.insert(Key::Borrowed(key), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        ser::SerializeMap::end(self)
+    }
+}
+
+enum SerializeStruct {
+    Hash(SerializeHash),
+    Blessed {
+        value: Scalar<'static>,
+        class: Cow<'static, str>,
+    },
+}
+
+impl ser::SerializeStruct for SerializeStruct {
+    type Ok = Scalar<'static>;
+    type Error = Error;
+
+    fn serialize_field<T>(&mut self, key: &'static str, value: &T) -> Result<()>
+    where
+        T: ?Sized + Serialize,
+    {
+        match self {
+            SerializeStruct::Hash(hash) => {
+                ser::SerializeStructVariant::serialize_field(hash, key, value)
+            }
+            SerializeStruct::Blessed { value: v, .. } if key == blessed::FIELDS[0] => {
+                *v = to_value(value)?;
+                Ok(())
+            }
+            SerializeStruct::Blessed { class, .. } => {
+                *class = Cow::Owned(value.serialize(KeySerializer)?);
+                Ok(())
+            }
+        }
+    }
+
+    fn end(self) -> Result<Self::Ok> {
+        match self {
+            SerializeStruct::Hash(hash) => ser::SerializeMap::end(hash),
+            SerializeStruct::Blessed { value, class } => {
+                Ok(Scalar::Object(Box::new(Object { class, value })))
+            }
+        }
+    }
+}
+
+/// Turns a hash key into the string Perl would use for it, allowing what the text serializer
+/// allows.
+struct KeySerializer;
+
+fn key_must_be_a_string() -> Error {
+    <Error as ser::Error>::custom("key must be a string")
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = Error;
+
+    type SerializeSeq = Impossible<String, Error>;
+    type SerializeTuple = Impossible<String, Error>;
+    type SerializeTupleStruct = Impossible<String, Error>;
+    type SerializeTupleVariant = Impossible<String, Error>;
+    type SerializeMap = Impossible<String, Error>;
+    type SerializeStruct = Impossible<String, Error>;
+    type SerializeStructVariant = Impossible<String, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<String> {
+        Ok(if v { "1" } else { "0" }.to_owned())
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<String> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<String> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<String> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<String> {
+        let mut key = String::new();
+        int_quote(&mut key, v);
+        Ok(key)
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<String> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<String> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<String> {
+        self.serialize_u64(u64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<String> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<String> {
+        let mut key = String::new();
+        float_quote(&mut key, v);
+        Ok(key)
+    }
+
+    fn serialize_char(self, v: char) -> Result<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> Result<String> {
+        Ok(v.to_owned())
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<String> {
+        String::from_utf8(v.to_owned()).map_err(|_| key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T>(self, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<String> {
+        Ok(variant.to_owned())
+    }
+
+    fn serialize_newtype_struct<T>(self, _name: &'static str, value: &T) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<String>
+    where
+        T: ?Sized + Serialize,
+    {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant> {
+        Err(key_must_be_a_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{from_value, parse, to_string, Blessed, PerlRef};
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    enum Shape {
+        Point,
+        Circle(f64),
+        Line(i32, i32),
+        Rect { w: u32, h: u32 },
+    }
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Drawing {
+        name: String,
+        shapes: Vec<Shape>,
+        tags: HashMap<u32, bool>,
+        owner: Blessed<HashMap<String, String>>,
+        layer: PerlRef<Option<u8>>,
+    }
+
+    #[test]
+    fn test_to_value() {
+        let drawing = Drawing {
+            name: "logo".to_owned(),
+            shapes: vec![
+                Shape::Point,
+                Shape::Circle(1.5),
+                Shape::Line(1, 2),
+                Shape::Rect { w: 3, h: 4 },
+            ],
+            tags: HashMap::from([(7, true)]),
+            owner: Blessed::new("Owner", HashMap::from([("id".to_owned(), "x".to_owned())])),
+            layer: PerlRef(None),
+        };
+        let scalar = to_value(&drawing).unwrap();
+        let mut expected = parse(
+            "{ name => 'logo', \
+               shapes => [ 'Point', { Circle => 1.5 }, { Line => [1, 2] }, \
+                           { Rect => { w => 3, h => 4 } } ], \
+               tags => { 7 => 1 }, \
+               owner => bless( { id => 'x' }, 'Owner' ), \
+               layer => \\undef }",
+        )
+        .unwrap();
+        expected["tags"]["7"] = Scalar::Bool(true);
+        assert_eq!(scalar, expected);
+        assert_eq!(from_value::<Drawing>(scalar).unwrap(), drawing);
+
+        assert_eq!(
+            to_value(&u64::MAX).unwrap(),
+            Scalar::from(u64::MAX.to_string())
+        );
+        assert!(to_value(&HashMap::from([(vec![1], 1)])).is_err());
+    }
+
+    #[test]
+    fn test_json_to_perl() {
+        let scalar: Scalar = serde_json::from_str(r#"{"tabs":[1,null,"x"]}"#).unwrap();
+        assert_eq!(to_string(&scalar).unwrap(), "{tabs=>[1,undef,'x']}");
+
+        // Booleans are written as numbers that read back as them.
+        let json = r#"{"open":true,"pinned":[false]}"#;
+        let scalar: Scalar = serde_json::from_str(json).unwrap();
+        let output = to_string(&scalar).unwrap();
+        assert_eq!(
+            parse(&output).unwrap(),
+            parse("{ open => 1, pinned => [0] }").unwrap()
+        );
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Tab {
+            open: bool,
+            pinned: Vec<bool>,
+        }
+        let mut de = crate::Deserializer::from_str(&output).strict_booleans(true);
+        let tab = Tab::deserialize(&mut de).unwrap();
+        assert_eq!(
+            tab,
+            Tab {
+                open: true,
+                pinned: vec![false]
+            }
+        );
+
+        let output = to_string(&to_value(&(true, false)).unwrap()).unwrap();
+        assert_eq!(output, "[1,0]");
+        assert_eq!(
+            crate::from_str::<(bool, bool)>(&output).unwrap(),
+            (true, false)
+        );
+
+        let json = r#"{"name":"Firefox","open":true,"tabs":[1,2.5,null]}"#;
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        let scalar: Scalar = serde_json::from_str(json).unwrap();
+        assert_eq!(from_value::<serde_json::Value>(scalar).unwrap(), value);
+    }
+}